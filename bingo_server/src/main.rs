@@ -0,0 +1,39 @@
+use std::env;
+use std::io::{stdin, BufRead};
+use std::str::FromStr;
+use y2021::bingo::BingoGame;
+use y2021::bingo_server::BingoServer;
+use y2021::utils;
+
+fn main() {
+    let args = env::args().collect::<Vec<String>>();
+    let input = args.get(1).map(|a| a.as_str()).unwrap_or("./input/input.txt");
+    let addr = args.get(2).map(|a| a.as_str()).unwrap_or("127.0.0.1:7878");
+
+    println!("Starting Bingo server");
+
+    let game = BingoGame::from_str(utils::read_input(input).as_str()).unwrap();
+    let server = BingoServer::bind(addr, game).unwrap_or_else(|_| panic!("Unable to listen on {}", addr));
+
+    println!("Listening on {}. Press enter to call the next number, or type q to stop.", server.local_addr().unwrap());
+
+    let handle = server.spawn();
+
+    for line in stdin().lock().lines() {
+        if line.map(|l| l.trim() == "q").unwrap_or(true) {
+            break;
+        }
+
+        match handle.call_next() {
+            Some(number) => println!(" - Called {} ({} players, {} winners)", number, handle.player_count(), handle.winners().len()),
+            None => {
+                println!("No numbers left to call.");
+                break;
+            },
+        }
+    }
+
+    for winner in handle.winners() {
+        println!("Place {}: card {} with {} on {}, score {}", winner.place, winner.card, winner.pattern.name, winner.number, winner.score);
+    }
+}
@@ -1,43 +1,13 @@
-use std::str::FromStr;
-use y2021::grid::Grid;
-use y2021::utils;
-use y2021::submarine;
+use std::fs::File;
+use std::io::BufReader;
+use y2021::diagnostics;
 
 fn main() {
     println!("Starting Day 3a");
-
-    let report = Grid::new(
-        utils::read_input("./input/input.txt").lines().map(|l| l.to_string()).collect::<Vec<String>>()
-    );
-
     println!("Reading diagnostic report.");
 
-    let mut result = (
-        Vec::new(),
-        Vec::new(),
-    );
-
-    for column in 0..report.cols {
-        let slice = report.vslice(column);
-        let counts = (
-          slice.iter().filter(|c| **c == '0').count(),
-          slice.iter().filter(|c| **c == '1').count(),
-        );
-
-        if counts.0 > counts.1 {
-            result.0.push('0');
-            result.1.push('1');
-        } else {
-            result.0.push('1');
-            result.1.push('0');
-        }
-    }
-
-    let values = (
-        utils::binary_to_number(result.0),
-        utils::binary_to_number(result.1),
-    );
+    let report = File::open("./input/input.txt").expect("Unable to read the file ./input/input.txt");
+    let result = diagnostics::power_consumption(BufReader::new(report)).unwrap();
 
-    println!("Diagnostic result: gamma={}, epsilon={}, result={}", values.0, values.1, values.0 * values.1);
-    // println!("Final solution: {}", position.horizontal * position.depth);
+    println!("Diagnostic result: gamma={}, epsilon={}, result={}", result.gamma, result.epsilon, result.power());
 }
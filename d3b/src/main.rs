@@ -4,6 +4,7 @@ use std::collections::hash_set::Iter;
 use std::str::FromStr;
 use std::collections::HashSet;
 use std::iter::Map;
+use y2021::bit_string::BitString;
 use y2021::grid::{Grid, Selection};
 use y2021::utils;
 use y2021::submarine;
@@ -15,7 +16,7 @@ fn select_indices(objects: Vec<char>, f: fn(&char) -> bool) -> HashSet<usize> {
         .collect::<HashSet<usize>>()
 }
 
-fn process(mut report: Grid, choose: fn((HashSet<usize>, HashSet<usize>)) -> Selection) -> u32 {
+fn process(mut report: Grid, choose: fn((HashSet<usize>, HashSet<usize>)) -> Selection) -> u64 {
     for position in 0..report.cols {
         let counts = (
             select_indices(report.vslice(position), |c| *c == '0'),
@@ -29,10 +30,10 @@ fn process(mut report: Grid, choose: fn((HashSet<usize>, HashSet<usize>)) -> Sel
         }
     }
 
-    y2021::utils::binary_to_number(report.hslice(0))
+    report.hslice(0).into_iter().collect::<String>().parse::<BitString>().unwrap().to_u64().unwrap()
 }
 
-fn get_oxygen_generator_rating(mut report: Grid) -> u32 {
+fn get_oxygen_generator_rating(mut report: Grid) -> u64 {
     process(report, |partitions| {
         if partitions.0.len() > partitions.1.len() {
             Selection{ keep: partitions.0, remove: partitions.1 }
@@ -42,7 +43,7 @@ fn get_oxygen_generator_rating(mut report: Grid) -> u32 {
     })
 }
 
-fn get_co2_scrubber_rating(mut report: Grid) -> u32 {
+fn get_co2_scrubber_rating(mut report: Grid) -> u64 {
     process(report, |partitions| {
         if partitions.0.len() <= partitions.1.len() {
             Selection{ keep: partitions.0, remove: partitions.1 }
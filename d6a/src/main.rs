@@ -6,15 +6,12 @@ fn main() {
     println!("Starting Day 6a");
     println!("Modeling lanternfish growth.");
 
-    let numbers = utils::read_input("./input/input.txt").trim()
-        .split(",").map(|l| l.parse::<u64>().unwrap()).collect::<Vec<u64>>();
+    let numbers = utils::parse_comma_separated::<u64>(utils::read_input("./input/input.txt").as_str()).unwrap();
     let days_to_simulate = 80;
 
-    let mut model = SpawningModel::new(numbers);
+    let mut model = SpawningModel::new(numbers).unwrap();
 
-    for i in 0..days_to_simulate {
-        model.one_day();
-    }
+    model.advance(days_to_simulate).unwrap();
 
     println!("Number of fish after {} days: {}", days_to_simulate, model.get_total());
 }
@@ -2,26 +2,18 @@ use std::str::FromStr;
 use std::collections::HashSet;
 use std::ops::Deref;
 use y2021::heightmap::{HeightMap};
+use y2021::point::Point2;
 use y2021::utils;
 
 #[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct Point {
-    pub x: i32,
-    pub y: i32,
+    pub position: Point2<i32>,
     pub value: u8,
 }
 
 impl Point {
-    fn move_x(&self, value: i32) -> Point {
-        let mut new = self.clone();
-        new.x = new.x as i32 + value;
-        return new;
-    }
-
-    fn move_y(&self, value: i32) -> Point {
-        let mut new = self.clone();
-        new.y = new.y as i32 + value;
-        return new;
+    fn at(height_map: &HeightMap, position: Point2<i32>) -> Point {
+        Point { position, value: height_map.get(position.x, position.y).unwrap_or(10) }
     }
 }
 
@@ -72,13 +64,8 @@ pub fn explore(height_map: &HeightMap, point: &Point, mut exploration: &mut Expl
     println!(" - Marking as basin.");
     exploration.keep(point);
 
-    for (x, y) in [
-        (point.x as i32 - 1, point.y as i32),
-        (point.x as i32 + 1, point.y as i32),
-        (point.x as i32, point.y as i32 - 1),
-        (point.x as i32, point.y as i32 + 1),
-    ] {
-        let point = Point { x, y, value: height_map.get(x, y).unwrap_or(10) };
+    for direction in Point2::<i32>::CARDINALS {
+        let point = Point::at(height_map, point.position + direction);
 
         if point.value < 9 && !exploration.previously_visited(&point) {
             exploration.keep(&point);
@@ -98,12 +85,12 @@ pub fn expand(height_map: &HeightMap, low_point: &Point) -> Basin {
     return exploration.basin.clone();
 }
 
-fn scan(height_map: &HeightMap) -> Vec<(i32, i32)> {
+fn scan(height_map: &HeightMap) -> Vec<Point2<i32>> {
     (0..height_map.rows).map(|x| x as i32).flat_map(|row| {
-        let init: Vec<(i32, i32)> = Vec::new();
+        let init: Vec<Point2<i32>> = Vec::new();
         height_map.hslice(row).into_iter().enumerate().fold(init, |mut result, (col, value)| {
             if height_map.is_low_point(col as i32, row) {
-                result.push((col as i32, row));
+                result.push(Point2::new(col as i32, row));
             }
             result
         })
@@ -122,9 +109,7 @@ fn main() {
         utils::read_input("./input/input.txt").trim()
     ).unwrap();
 
-    let low_points: Vec<Point> = scan(&input).into_iter().map(|coord| {
-        Point { x: coord.0, y: coord.1, value: input.get(coord.0, coord.1).unwrap() }
-    }).collect();
+    let low_points: Vec<Point> = scan(&input).into_iter().map(|position| Point::at(&input, position)).collect();
 
     let mut basins = extract_basins(&input, low_points);
 
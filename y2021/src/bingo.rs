@@ -1,107 +1,1288 @@
-use std::cmp::max;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use super::bit_string::BitString;
+use super::point::Point2;
+use super::random::Rng;
+use super::utils::{self, Section};
+
+#[derive(PartialEq, Debug)]
+pub enum BingoParseError {
+    MissingCallLine,
+    MissingCardSeparator(usize),
+    InvalidCall { line: usize, text: String },
+    InvalidNumber { card: u32, line: usize, text: String },
+    NoCards,
+    EmptyCard(u32),
+    RaggedCard { card: u32, line: usize, expected: usize, actual: usize },
+    MismatchedCardSize { card: u32, line: usize, expected: (usize, usize), actual: (usize, usize) },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum BingoEvent {
+    NumberCalled { call: usize, number: u32 },
+    CardMarked { card: u32, x: usize, y: usize },
+    CardWon { card: u32, call: usize, number: u32, score: u64, place: usize, pattern: Pattern },
+    Exhausted { remaining_numbers: Vec<u32>, losers: Vec<u32> },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BingoWinner {
+    pub card: u32,
+    pub call: usize,
+    pub number: u32,
+    pub score: u64,
+    pub place: usize,
+    pub pattern: Pattern,
+}
 
 #[derive(Clone, Debug)]
 pub struct BingoResult{
-    pub last_number: u32,
-    pub remaining_numbers: Vec<usize>,
-    pub winners: Vec<Bingo>,
+    pub remaining_numbers: Vec<u32>,
+    pub winners: Vec<BingoWinner>,
     pub losers: Vec<Bingo>,
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub struct Pattern {
+    pub name: String,
+    pub cells: Vec<Point2<usize>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum WinRule {
+    Rows,
+    Columns,
+    Diagonals,
+    FourCorners,
+    Blackout,
+    XShape,
+    Mask(String, Vec<Point2<usize>>),
+    AnyOf(Vec<WinRule>),
+}
+
+impl WinRule {
+    pub fn standard() -> WinRule {
+        WinRule::AnyOf(vec![WinRule::Rows, WinRule::Columns])
+    }
+
+    // Builds a mask from a drawing of the card where '#' or 'X' marks a required cell.
+    pub fn mask(name: &str, drawing: &str) -> WinRule {
+        let cells = drawing.lines().enumerate().flat_map(|(y, line)| {
+            line.chars().enumerate().filter(|(_, c)| matches!(c, '#' | 'X' | 'x')).map(move |(x, _)| Point2::new(x, y))
+        }).collect();
+
+        WinRule::Mask(name.to_string(), cells)
+    }
+
+    pub fn patterns(&self, cols: usize, rows: usize) -> Vec<Pattern> {
+        let pattern = |name: &str, cells: Vec<Point2<usize>>| Pattern { name: name.to_string(), cells };
+        let diagonal = (0..cols).map(|i| Point2::new(i, i)).collect::<Vec<Point2<usize>>>();
+        let anti_diagonal = (0..cols).map(|i| Point2::new(cols - 1 - i, i)).collect::<Vec<Point2<usize>>>();
+
+        match self {
+            WinRule::Rows => (0..rows).map(|y| pattern(
+                format!("row {}", y).as_str(), (0..cols).map(|x| Point2::new(x, y)).collect(),
+            )).collect(),
+            WinRule::Columns => (0..cols).map(|x| pattern(
+                format!("column {}", x).as_str(), (0..rows).map(|y| Point2::new(x, y)).collect(),
+            )).collect(),
+            WinRule::Diagonals if cols == rows && cols > 0 => vec![
+                pattern("diagonal", diagonal),
+                pattern("anti-diagonal", anti_diagonal),
+            ],
+            WinRule::FourCorners if cols > 0 && rows > 0 => vec![
+                pattern("four corners", [(0, 0), (cols - 1, 0), (0, rows - 1), (cols - 1, rows - 1)].map(Point2::from).to_vec()),
+            ],
+            WinRule::Blackout if cols > 0 && rows > 0 => vec![
+                pattern("blackout", (0..rows).flat_map(|y| (0..cols).map(move |x| Point2::new(x, y))).collect()),
+            ],
+            WinRule::XShape if cols == rows && cols > 0 => vec![
+                pattern("x", diagonal.into_iter().chain(anti_diagonal.into_iter().filter(|c| c.x != c.y)).collect()),
+            ],
+            WinRule::Mask(name, cells) if !cells.is_empty() && cells.iter().all(|c| c.x < cols && c.y < rows) => vec![
+                pattern(name.as_str(), cells.clone()),
+            ],
+            WinRule::AnyOf(rules) => rules.iter().flat_map(|rule| rule.patterns(cols, rows)).collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl Default for WinRule {
+    fn default() -> Self {
+        WinRule::standard()
+    }
+}
+
+// Cells are addressed as (x, y) throughout: x is the column in 0..cols and y is the
+// row in 0..rows, so a card with 3 lines of 5 numbers has cols = 5 and rows = 3.
 #[derive(Clone, Debug)]
 pub struct Bingo {
     pub id: u32,
     pub rows: usize,
     pub cols: usize,
-    pub markers: HashSet<(usize, usize)>,
-    data: Vec<Vec<u32>>,
+    data: Vec<u32>,
+    marked: BitString,
+    line_marks: Vec<u32>,
+    complete_lines: usize,
 }
 
 impl Bingo {
+    fn from_data(id: u32, cols: usize, rows: usize, data: Vec<u32>) -> Bingo {
+        assert_eq!(data.len(), cols * rows);
+
+        Bingo {
+            id,
+            rows,
+            cols,
+            data,
+            marked: BitString::zeros(cols * rows),
+            line_marks: vec![0; rows + cols],
+            complete_lines: 0,
+        }
+    }
+
     pub fn calculate_solution(&self, winning_number: u32) -> u32 {
-        let mut sum = 0;
+        let unmarked: u32 = self.data.iter().zip(self.marked.iter())
+            .filter(|(_, marked)| !marked)
+            .map(|(n, _)| *n)
+            .sum();
+
+        unmarked * winning_number
+    }
+
+    fn cell_index(&self, x: usize, y: usize) -> usize {
+        assert!(x < self.cols && y < self.rows, "cell ({}, {}) is outside a {}x{} card", x, y, self.cols, self.rows);
+
+        y * self.cols + x
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> u32 {
+        self.data[self.cell_index(x, y)]
+    }
+
+    pub fn is_marked(&self, x: usize, y: usize) -> bool {
+        self.marked.get(self.cell_index(x, y)).unwrap_or(false)
+    }
+
+    pub fn marked_cells(&self) -> impl Iterator<Item = Point2<usize>> + '_ {
+        self.cells().filter(move |c| self.is_marked(c.x, c.y))
+    }
+
+    // A full row or column is counted the moment its last cell is marked, so this
+    // never has to look at the cells themselves.
+    pub fn is_winning(&self) -> bool {
+        self.complete_lines > 0
+    }
+
+    pub fn winning_pattern(&self, rule: &WinRule) -> Option<Pattern> {
+        self.first_complete(&rule.patterns(self.cols, self.rows)).cloned()
+    }
+
+    fn first_complete<'p>(&self, patterns: &'p [Pattern]) -> Option<&'p Pattern> {
+        patterns.iter().find(|pattern| pattern.cells.iter().all(|c| self.is_marked(c.x, c.y)))
+    }
+
+    pub fn mark(&mut self, x: usize, y: usize) -> bool {
+        let cell = self.cell_index(x, y);
+
+        self.mark_cell(cell)
+    }
+
+    pub fn mark_number(&mut self, number: u32) -> usize {
+        let cells = self.data.iter().enumerate()
+            .filter(|(_, n)| **n == number)
+            .map(|(cell, _)| cell)
+            .collect::<Vec<usize>>();
+
+        cells.into_iter().filter(|cell| self.mark_cell(*cell)).count()
+    }
+
+    fn mark_cell(&mut self, cell: usize) -> bool {
+        if self.marked.get(cell) != Some(false) {
+            return false;
+        }
+
+        let (row, col) = self.lines_of(cell);
+
+        self.marked.set(cell, true);
+        self.line_marks[row] += 1;
+        self.line_marks[col] += 1;
+
+        if self.is_line_complete(row) {
+            self.complete_lines += 1;
+        }
+
+        if self.is_line_complete(col) {
+            self.complete_lines += 1;
+        }
+
+        true
+    }
+
+    // Lines are numbered the way WinRule::standard() numbers its patterns: rows first,
+    // then columns.
+    fn lines_of(&self, cell: usize) -> (usize, usize) {
+        (cell / self.cols, self.rows + cell % self.cols)
+    }
+
+    fn is_line_complete(&self, line: usize) -> bool {
+        self.line_marks[line] as usize == if line < self.rows { self.cols } else { self.rows }
+    }
+
+    pub fn hslice(&self, row: usize) -> Vec<u32> {
+        assert!(row < self.rows);
+
+        self.data[row * self.cols..(row + 1) * self.cols].to_vec()
+    }
+
+    pub fn vslice(&self, column: usize) -> Vec<u32> {
+        assert!(column < self.cols);
+
+        self.data.iter().skip(column).step_by(self.cols).copied().collect()
+    }
+
+    pub fn cells(&self) -> impl Iterator<Item = Point2<usize>> + '_ {
+        (0..self.rows).flat_map(move |y| (0..self.cols).map(move |x| Point2::new(x, y)))
+    }
+
+    pub fn random(rng: &mut Rng, id: u32, cols: usize, rows: usize, max_number: u32) -> Bingo {
+        assert!(cols * rows <= max_number as usize + 1, "not enough distinct numbers for a {}x{} card", cols, rows);
+
+        let mut numbers = (0..=max_number).collect::<Vec<u32>>();
+        rng.shuffle(&mut numbers);
+        numbers.truncate(cols * rows);
+
+        Bingo::from_data(id, cols, rows, numbers)
+    }
+
+    pub fn new(input: Vec<String>, id: u32) -> Result<Bingo, BingoParseError> {
+        Bingo::from_section(&Section { start_line: 1, lines: input }, id)
+    }
+
+    fn from_section(section: &Section, id: u32) -> Result<Bingo, BingoParseError> {
+        let mut data: Vec<Vec<u32>> = Vec::new();
+
+        for (i, line) in section.lines.iter().enumerate() {
+            data.push(line.split_whitespace().map(|number| {
+                number.parse::<u32>().map_err(|_| BingoParseError::InvalidNumber {
+                    card: id,
+                    line: section.start_line + i,
+                    text: number.to_string(),
+                })
+            }).collect::<Result<Vec<u32>, BingoParseError>>()?);
+        }
+
+        let rows = data.len();
+        let cols = data.first().map(|row| row.len()).unwrap_or(0);
+
+        if cols == 0 {
+            return Err(BingoParseError::EmptyCard(id));
+        }
+
+        if let Some((i, row)) = data.iter().enumerate().find(|(_, row)| row.len() != cols) {
+            return Err(BingoParseError::RaggedCard { card: id, line: section.start_line + i, expected: cols, actual: row.len() });
+        }
+
+        Ok(Bingo::from_data(id, cols, rows, data.concat()))
+    }
+}
+
+// Scores saturate at u64::MAX rather than overflow, whatever the strategy.
+pub trait ScoringStrategy {
+    fn score(&self, card: &Bingo, pattern: &Pattern, number: u32, call: usize) -> u64;
+}
+
+impl<F> ScoringStrategy for F where F: Fn(&Bingo, &Pattern, u32, usize) -> u64 {
+    fn score(&self, card: &Bingo, pattern: &Pattern, number: u32, call: usize) -> u64 {
+        self(card, pattern, number, call)
+    }
+}
+
+fn sum_cells(card: &Bingo, marked: bool) -> u64 {
+    card.data.iter().zip(card.marked.iter()).filter(|(_, m)| *m == marked).fold(0, |a, (n, _)| a.saturating_add(*n as u64))
+}
+
+// Sum of the unmarked numbers multiplied by the number that completed the card.
+pub struct StandardScoring;
+
+impl ScoringStrategy for StandardScoring {
+    fn score(&self, card: &Bingo, _: &Pattern, number: u32, _: usize) -> u64 {
+        sum_cells(card, false).saturating_mul(number as u64)
+    }
+}
+
+pub struct SumOfMarked;
+
+impl ScoringStrategy for SumOfMarked {
+    fn score(&self, card: &Bingo, _: &Pattern, _: u32, _: usize) -> u64 {
+        sum_cells(card, true)
+    }
+}
+
+pub struct WinningLineProduct;
+
+impl ScoringStrategy for WinningLineProduct {
+    fn score(&self, card: &Bingo, pattern: &Pattern, _: u32, _: usize) -> u64 {
+        pattern.cells.iter().map(|c| card.get(c.x, c.y) as u64).fold(1, |a, b| a.saturating_mul(b))
+    }
+}
+
+// The standard score scaled by how many numbers had been called, so late wins score higher.
+pub struct WeightedByCall;
+
+impl ScoringStrategy for WeightedByCall {
+    fn score(&self, card: &Bingo, pattern: &Pattern, number: u32, call: usize) -> u64 {
+        StandardScoring.score(card, pattern, number, call).saturating_mul(call as u64 + 1)
+    }
+}
+
+impl Display for Bingo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", CardRenderer::default().render(self))
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct CardRenderer {
+    pub ansi: bool,
+}
+
+impl CardRenderer {
+    pub fn ansi() -> CardRenderer {
+        CardRenderer { ansi: true }
+    }
+
+    fn number_width(cards: &[&Bingo]) -> usize {
+        cards.iter()
+            .flat_map(|card| card.cells().map(|c| card.get(c.x, c.y)))
+            .map(|n| n.to_string().len())
+            .max()
+            .unwrap_or(1)
+    }
+
+    // Every cell takes width + 2 columns, so marked and unmarked cells line up
+    // whether the marker is a pair of brackets or an ANSI highlight.
+    fn cell(&self, value: u32, marked: bool, width: usize) -> String {
+        match (marked, self.ansi) {
+            (true, true) => format!(" \x1b[1;7m{:>width$}\x1b[0m ", value, width = width),
+            (true, false) => format!("[{:>width$}]", value, width = width),
+            (false, _) => format!(" {:>width$} ", value, width = width),
+        }
+    }
+
+    fn lines(&self, card: &Bingo, width: usize) -> Vec<String> {
+        (0..card.rows).map(|y| {
+            (0..card.cols).map(|x| self.cell(card.get(x, y), card.is_marked(x, y), width)).collect()
+        }).collect()
+    }
+
+    // Titles never run past their card, so the columns of a board stay aligned. Narrow
+    // cards fall back to "#id" before the title is cut.
+    fn header(card: &Bingo, pad: usize) -> String {
+        let star = if card.is_winning() { " *" } else { "" };
+        let title = [format!("Card {}{}", card.id, star), format!("#{}{}", card.id, star)].into_iter()
+            .find(|title| title.chars().count() <= pad)
+            .unwrap_or_else(|| format!("#{}", card.id).chars().take(pad).collect());
+
+        format!("{:<pad$}", title, pad = pad)
+    }
+
+    pub fn render(&self, card: &Bingo) -> String {
+        self.lines(card, CardRenderer::number_width(&[card])).join("\n")
+    }
+
+    pub fn render_board(&self, cards: &[Bingo], per_row: usize) -> String {
+        let width = CardRenderer::number_width(&cards.iter().collect::<Vec<&Bingo>>());
+        let mut bands: Vec<String> = Vec::new();
+
+        for band in cards.chunks(per_row.max(1)) {
+            let mut output: Vec<String> = Vec::new();
+            let headers = band.iter().map(|card| CardRenderer::header(card, card.cols * (width + 2))).collect::<Vec<String>>();
+
+            output.push(headers.join("  ").trim_end().to_string());
+
+            let rendered = band.iter().map(|card| self.lines(card, width)).collect::<Vec<Vec<String>>>();
+            let rows = band.iter().map(|card| card.rows).max().unwrap_or(0);
+
+            for y in 0..rows {
+                let line = band.iter().zip(rendered.iter()).map(|(card, lines)| {
+                    lines.get(y).cloned().unwrap_or_else(|| " ".repeat(card.cols * (width + 2)))
+                }).collect::<Vec<String>>();
+
+                output.push(line.join("  ").trim_end().to_string());
+            }
+
+            bands.push(output.join("\n"));
+        }
+
+        bands.join("\n\n")
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BingoGame {
+    pub numbers: Vec<u32>,
+    pub cards: Vec<Bingo>,
+    pub rule: WinRule,
+}
+
+impl FromStr for BingoGame {
+    type Err = BingoParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut sections = utils::sections(s).into_iter();
+        let calls = sections.next().ok_or(BingoParseError::MissingCallLine)?;
+
+        if calls.lines.len() > 1 {
+            return Err(BingoParseError::MissingCardSeparator(calls.start_line + 1));
+        }
+
+        let numbers = utils::parse_comma_separated::<u32>(calls.lines[0].as_str()).map_err(|e| match e {
+            utils::LineParseError::Invalid { text, .. } => BingoParseError::InvalidCall { line: calls.start_line, text },
+            utils::LineParseError::Io(_) => BingoParseError::MissingCallLine,
+        })?;
+
+        let mut cards: Vec<Bingo> = Vec::new();
+
+        for (id, section) in sections.enumerate() {
+            let card = Bingo::from_section(&section, id as u32)?;
+
+            let (cols, rows) = cards.first().map(|c| (c.cols, c.rows)).unwrap_or((card.cols, card.rows));
+
+            if (card.cols, card.rows) != (cols, rows) {
+                return Err(BingoParseError::MismatchedCardSize {
+                    card: card.id,
+                    line: section.start_line,
+                    expected: (cols, rows),
+                    actual: (card.cols, card.rows),
+                });
+            }
+
+            cards.push(card);
+        }
+
+        if cards.is_empty() {
+            return Err(BingoParseError::NoCards);
+        }
+
+        Ok(BingoGame { numbers, cards, rule: WinRule::standard() })
+    }
+}
+
+impl BingoGame {
+    pub fn random(rng: &mut Rng, config: &SimulationConfig) -> BingoGame {
+        let mut numbers = (0..=config.max_number).collect::<Vec<u32>>();
+        rng.shuffle(&mut numbers);
+
+        let cards = (0..config.cards)
+            .map(|id| Bingo::random(rng, id as u32, config.cols, config.rows, config.max_number))
+            .collect();
+
+        BingoGame { numbers, cards, rule: config.rule.clone() }
+    }
+
+    pub fn with_rule(mut self, rule: WinRule) -> BingoGame {
+        self.rule = rule;
+        self
+    }
+
+    pub fn run(&self) -> BingoRun<'_> {
+        self.run_with(&StandardScoring)
+    }
+
+    pub fn run_with<'a>(&'a self, scoring: &'a dyn ScoringStrategy) -> BingoRun<'a> {
+        let mut index: HashMap<u32, Vec<(usize, usize)>> = HashMap::new();
+        let mut shapes: Vec<PatternIndex> = Vec::new();
+        let mut shape_of: Vec<usize> = Vec::with_capacity(self.cards.len());
+        let mut offsets: Vec<usize> = Vec::with_capacity(self.cards.len());
+        let mut progress_len = 0;
+
+        for (c, card) in self.cards.iter().enumerate() {
+            for (cell, number) in card.data.iter().enumerate() {
+                index.entry(*number).or_default().push((c, cell));
+            }
+
+            let shape = PatternIndex::find_or_insert(&mut shapes, &self.rule, card);
+
+            shape_of.push(shape);
+            offsets.push(progress_len);
+            progress_len += shapes[shape].patterns.len();
+        }
+
+        BingoRun {
+            numbers: &self.numbers,
+            scoring,
+            cards: self.cards.clone(),
+            index,
+            shapes,
+            shape_of,
+            offsets,
+            progress: vec![0; progress_len],
+            won: vec![false; self.cards.len()],
+            winners: 0,
+            call: 0,
+            events: VecDeque::new(),
+            finished: false,
+        }
+    }
+
+    pub fn play(&self) -> BingoResult {
+        self.play_with(&StandardScoring)
+    }
+
+    // Without the event stream every card can be settled on its own: a pattern is
+    // complete on the latest call among its cells and a card wins on the earliest of
+    // those, so each card is only marked once its winning call is known.
+    pub fn play_with(&self, scoring: &dyn ScoringStrategy) -> BingoResult {
+        let mut first_call: HashMap<u32, usize> = HashMap::with_capacity(self.numbers.len());
+        let mut shapes: Vec<PatternIndex> = Vec::new();
+        let mut calls: Vec<usize> = Vec::new();
+        let mut winners: Vec<BingoWinner> = Vec::new();
+        let mut losers = Vec::new();
 
-        for x in 0..self.cols {
-            for y in 0..self.rows {
-                if !self.markers.contains(&(x, y)) {
-                    sum += self.get(x, y);
+        for (call, number) in self.numbers.iter().enumerate() {
+            first_call.entry(*number).or_insert(call);
+        }
+
+        let settle = |card: &Bingo, calls: &[usize], last: usize| {
+            let mut marked = card.clone();
+
+            for (cell, call) in calls.iter().enumerate() {
+                if *call <= last {
+                    marked.mark_cell(cell);
                 }
             }
+
+            marked
+        };
+
+        for card in self.cards.iter() {
+            let shape = PatternIndex::find_or_insert(&mut shapes, &self.rule, card);
+
+            calls.clear();
+            calls.extend(card.data.iter().map(|number| first_call.get(number).copied().unwrap_or(usize::MAX)));
+
+            let win = shapes[shape].patterns.iter().enumerate()
+                .filter_map(|(p, pattern)| pattern.cells.iter().map(|cell| calls[cell.y * card.cols + cell.x]).max().map(|call| (call, p)))
+                .min();
+
+            match win {
+                Some((call, p)) if call != usize::MAX => {
+                    let marked = settle(card, &calls, call);
+                    let pattern = &shapes[shape].patterns[p];
+                    let number = self.numbers[call];
+                    let score = scoring.score(&marked, pattern, number, call);
+
+                    winners.push(BingoWinner { card: card.id, call, number, score, place: 0, pattern: pattern.clone() });
+                },
+                _ => losers.push(settle(card, &calls, self.numbers.len())),
+            }
         }
 
-        return sum * winning_number;
+        // Cards are visited in order, so a stable sort keeps ties on a call in card
+        // order, the same as the run reports them.
+        winners.sort_by_key(|winner| winner.call);
+
+        for i in 0..winners.len() {
+            winners[i].place = match i {
+                0 => 1,
+                _ if winners[i - 1].call == winners[i].call => winners[i - 1].place,
+                _ => i + 1,
+            };
+        }
+
+        let remaining_from = match winners.last() {
+            _ if !losers.is_empty() => self.numbers.len(),
+            Some(winner) => winner.call + 1,
+            None => 0,
+        };
+
+        BingoResult { remaining_numbers: self.numbers[remaining_from..].to_vec(), winners, losers }
     }
+}
 
-    pub fn get(&self, x: usize, y: usize) -> u32 {
-        assert!(y >= 0 && y < self.data.len());
-        assert!(x >= 0 && x < self.data[y].len());
+// The cells of every pattern a rule produces for one card shape, indexed the other
+// way round so that marking a cell only touches the patterns that contain it.
+struct PatternIndex {
+    cols: usize,
+    rows: usize,
+    standard: bool,
+    patterns: Vec<Pattern>,
+    sizes: Vec<usize>,
+    by_cell: Vec<Vec<usize>>,
+}
+
+impl PatternIndex {
+    fn new(rule: &WinRule, cols: usize, rows: usize) -> PatternIndex {
+        let patterns = rule.patterns(cols, rows);
+        let mut sizes = vec![0; patterns.len()];
+        let mut by_cell: Vec<Vec<usize>> = vec![Vec::new(); cols * rows];
+
+        for (p, pattern) in patterns.iter().enumerate() {
+            for c in pattern.cells.iter() {
+                let cell = &mut by_cell[c.y * cols + c.x];
 
-        self.data[y][x]
+                if !cell.contains(&p) {
+                    cell.push(p);
+                    sizes[p] += 1;
+                }
+            }
+        }
+
+        PatternIndex { cols, rows, standard: *rule == WinRule::standard(), patterns, sizes, by_cell }
     }
 
-    pub fn is_winning(&self) -> bool {
-        for y in 0..self.rows {
-            if (0..self.cols).map(|x| self.markers.contains(&(x, y))).all(|o| o) {
-                return true;
+    fn find_or_insert(shapes: &mut Vec<PatternIndex>, rule: &WinRule, card: &Bingo) -> usize {
+        match shapes.iter().position(|s| (s.cols, s.rows) == (card.cols, card.rows)) {
+            Some(shape) => shape,
+            None => {
+                shapes.push(PatternIndex::new(rule, card.cols, card.rows));
+                shapes.len() - 1
+            },
+        }
+    }
+}
+
+pub struct BingoRun<'a> {
+    numbers: &'a [u32],
+    scoring: &'a dyn ScoringStrategy,
+    cards: Vec<Bingo>,
+    index: HashMap<u32, Vec<(usize, usize)>>,
+    shapes: Vec<PatternIndex>,
+    shape_of: Vec<usize>,
+    offsets: Vec<usize>,
+    progress: Vec<usize>,
+    won: Vec<bool>,
+    winners: usize,
+    call: usize,
+    events: VecDeque<BingoEvent>,
+    finished: bool,
+}
+
+impl<'a> BingoRun<'a> {
+    pub fn cards(&self) -> &[Bingo] {
+        &self.cards
+    }
+
+    fn step(&mut self) {
+        if self.call >= self.numbers.len() || self.winners == self.cards.len() {
+            self.events.push_back(BingoEvent::Exhausted {
+                remaining_numbers: self.numbers[self.call..].to_vec(),
+                losers: self.cards.iter().zip(self.won.iter()).filter(|(_, won)| !**won).map(|(card, _)| card.id).collect(),
+            });
+            self.finished = true;
+
+            return;
+        }
+
+        let number = self.numbers[self.call];
+        let place = self.winners + 1;
+        let mut completed: Vec<(usize, usize)> = Vec::new();
+        let mut complete = |c: usize, p: usize| match completed.last_mut() {
+            Some((last, pattern)) if *last == c => *pattern = (*pattern).min(p),
+            _ => completed.push((c, p)),
+        };
+
+        self.events.push_back(BingoEvent::NumberCalled { call: self.call, number });
+
+        for (c, cell) in self.index.get(&number).into_iter().flatten() {
+            if self.won[*c] || !self.cards[*c].mark_cell(*cell) {
+                continue;
+            }
+
+            let card = &self.cards[*c];
+            let shape = &self.shapes[self.shape_of[*c]];
+
+            self.events.push_back(BingoEvent::CardMarked { card: card.id, x: cell % card.cols, y: cell / card.cols });
+
+            // The card already counts its own rows and columns, so the standard rule
+            // needs no extra bookkeeping.
+            if shape.standard {
+                let (row, col) = card.lines_of(*cell);
+
+                if let Some(p) = [row, col].into_iter().find(|line| card.is_line_complete(*line)) {
+                    complete(*c, p);
+                }
+
+                continue;
+            }
+
+            for p in shape.by_cell[*cell].iter() {
+                let progress = &mut self.progress[self.offsets[*c] + p];
+                *progress += 1;
+
+                if *progress == shape.sizes[*p] {
+                    complete(*c, *p);
+                }
             }
         }
 
-        for x in 0..self.cols {
-            if (0..self.rows).map(|y| self.markers.contains(&(x, y))).all(|o| o) {
-                return true;
+        for (c, p) in completed {
+            let pattern = &self.shapes[self.shape_of[c]].patterns[p];
+
+            self.won[c] = true;
+            self.winners += 1;
+            self.events.push_back(BingoEvent::CardWon {
+                card: self.cards[c].id,
+                call: self.call,
+                number,
+                score: self.scoring.score(&self.cards[c], pattern, number, self.call),
+                place,
+                pattern: pattern.clone(),
+            });
+        }
+
+        self.call += 1;
+    }
+}
+
+impl<'a> Iterator for BingoRun<'a> {
+    type Item = BingoEvent;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.events.is_empty() && !self.finished {
+            self.step();
+        }
+
+        self.events.pop_front()
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SimulationConfig {
+    pub cards: usize,
+    pub cols: usize,
+    pub rows: usize,
+    pub max_number: u32,
+    pub rule: WinRule,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        SimulationConfig { cards: 100, cols: 5, rows: 5, max_number: 99, rule: WinRule::standard() }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SimulationReport {
+    pub games: usize,
+    pub no_winner: usize,
+    pub tied_first_wins: usize,
+    pub calls_to_first_win: BTreeMap<usize, usize>,
+    pub winners_on_first_win: BTreeMap<usize, usize>,
+}
+
+impl SimulationReport {
+    pub fn mean_calls_to_first_win(&self) -> f64 {
+        let (games, calls) = self.calls_to_first_win.iter()
+            .fold((0, 0), |(games, calls), (c, n)| (games + n, calls + c * n));
+
+        if games == 0 { 0.0 } else { calls as f64 / games as f64 }
+    }
+
+    pub fn tie_rate(&self) -> f64 {
+        let won = self.games - self.no_winner;
+
+        if won == 0 { 0.0 } else { self.tied_first_wins as f64 / won as f64 }
+    }
+}
+
+pub fn simulate(config: &SimulationConfig, games: usize, seed: u64) -> SimulationReport {
+    let mut rng = Rng::new(seed);
+    let mut report = SimulationReport { games, ..SimulationReport::default() };
+
+    for _ in 0..games {
+        let game = BingoGame::random(&mut rng.fork(), config);
+        let mut first_win: Option<(usize, usize)> = None;
+
+        for event in game.run() {
+            match (event, first_win) {
+                (BingoEvent::CardWon { call, .. }, None) => first_win = Some((call, 1)),
+                (BingoEvent::CardWon { call, .. }, Some((first, n))) if call == first => first_win = Some((first, n + 1)),
+                (BingoEvent::CardWon { .. }, Some(_)) | (BingoEvent::Exhausted { .. }, _) => break,
+                _ => (),
             }
         }
 
-        return false;
+        match first_win {
+            Some((call, winners)) => {
+                *report.calls_to_first_win.entry(call + 1).or_default() += 1;
+                *report.winners_on_first_win.entry(winners).or_default() += 1;
+
+                if winners > 1 {
+                    report.tied_first_wins += 1;
+                }
+            },
+            None => report.no_winner += 1,
+        }
     }
 
-    pub fn mark(&mut self, x: usize, y: usize) {
-        assert!(y >= 0 && y < self.data.len());
-        assert!(x >= 0 && x < self.data[y].len());
+    report
+}
+
+// Searches call orders drawn from a game's numbers. Every pattern a card can win with
+// is reduced to the set of pool numbers it needs; candidate orders are then replayed
+// through BingoGame::play so the answer always agrees with the real win detection.
+pub struct CallOrderSolver<'a> {
+    game: &'a BingoGame,
+    pool: Vec<u32>,
+    patterns: Vec<Vec<BitString>>,
+}
+
+impl<'a> CallOrderSolver<'a> {
+    pub fn new(game: &'a BingoGame) -> CallOrderSolver<'a> {
+        let mut pool: Vec<u32> = Vec::new();
+
+        for number in game.numbers.iter() {
+            if !pool.contains(number) {
+                pool.push(*number);
+            }
+        }
+
+        let positions = pool.iter().enumerate().map(|(i, n)| (*n, i)).collect::<HashMap<u32, usize>>();
+        let patterns = game.cards.iter().map(|card| {
+            let mut sets: Vec<BitString> = Vec::new();
+
+            for pattern in game.rule.patterns(card.cols, card.rows) {
+                let mut set = BitString::zeros(pool.len());
+                let achievable = pattern.cells.iter().all(|c| match positions.get(&card.get(c.x, c.y)) {
+                    Some(i) => {
+                        set.set(*i, true);
+                        true
+                    },
+                    None => false,
+                });
+
+                if achievable && !sets.contains(&set) {
+                    sets.push(set);
+                }
+            }
 
-        self.markers.insert((x, y));
+            sets.sort_by_key(|set| set.count_ones());
+            sets
+        }).collect();
+
+        CallOrderSolver { game, pool, patterns }
     }
 
-    pub fn hslice(&self, row: usize) -> Vec<u32> {
-        assert!(row >= 0 && row < self.data.len());
+    fn numbers(&self, set: &BitString) -> Vec<u32> {
+        set.iter().zip(self.pool.iter()).filter(|(called, _)| *called).map(|(_, n)| *n).collect()
+    }
 
-        self.data[row].clone()
+    fn replay(&self, order: &[u32]) -> Vec<BingoWinner> {
+        BingoGame { numbers: order.to_vec(), cards: self.game.cards.clone(), rule: self.game.rule.clone() }.play().winners
     }
 
-    pub fn vslice(&self, column: usize) -> Vec<u32> {
-        assert!(column >= 0);
+    // The shortest order after which `card` is the only winner. None means no such
+    // order exists: every way of completing the card completes another card too.
+    pub fn shortest_first_win(&self, card: u32) -> Option<Vec<u32>> {
+        let k = self.game.cards.iter().position(|c| c.id == card)?;
+
+        self.patterns[k].iter().map(|set| self.numbers(set)).find(|order| {
+            let winners = self.replay(order);
 
-        let mut result = Vec::new();
+            winners.len() == 1 && winners[0].card == card
+        })
+    }
 
-        for row in &self.data {
-            assert!(column < row.len());
+    // An order in which every other card wins before `card` does. None means no such
+    // order exists.
+    pub fn last_win(&self, card: u32) -> Option<Vec<u32>> {
+        let k = self.game.cards.iter().position(|c| c.id == card)?;
+        let others = (0..self.game.cards.len()).filter(|c| *c != k).collect::<Vec<usize>>();
+        let mut seen: HashSet<BitString> = HashSet::new();
 
-            result.push(row[column]);
+        if self.patterns[k].is_empty() || others.iter().any(|c| self.patterns[*c].is_empty()) {
+            return None;
         }
 
-        result
+        self.search_last(k, &others, BitString::zeros(self.pool.len()), &mut seen)
     }
 
-    pub fn new(input: Vec<String>, id: u32) -> Bingo {
-        let mut rows: usize = 0;
-        let mut cols: usize = 0;
-        let mut data: Vec<Vec<u32>> = Vec::new();
+    // `excluded` collects numbers that are held back until the other cards have all
+    // won. Once it hits every pattern of card k, k cannot finish early, so it only
+    // remains to find a pattern for each other card that avoids those numbers.
+    fn search_last(&self, k: usize, others: &[usize], excluded: BitString, seen: &mut HashSet<BitString>) -> Option<Vec<u32>> {
+        if !seen.insert(excluded.clone()) {
+            return None;
+        }
+
+        let avoids = |set: &BitString| (set & &excluded).count_ones() == 0;
+        let mut chosen: Vec<&BitString> = Vec::new();
+
+        for c in others {
+            chosen.push(self.patterns[*c].iter().find(|set| avoids(set))?);
+        }
+
+        let unhit = match self.patterns[k].iter().find(|set| avoids(set)) {
+            Some(unhit) => unhit,
+            None => {
+                let others_done = chosen.iter().fold(BitString::zeros(self.pool.len()), |result, set| &result | set);
+                let finish = self.patterns[k].iter().min_by_key(|set| (&(*set | &others_done) ^ &others_done).count_ones())?;
+                let mut order = self.numbers(&others_done);
 
-        input.clone().iter().enumerate().for_each(|(i, line)| {
-            rows = max(rows, (i + 1));
+                order.extend(self.numbers(&(&(finish | &others_done) ^ &others_done)));
 
-            line.split_whitespace().enumerate().for_each(|(j, number)| {
-                if i >= data.len() {
-                    &data.push(Vec::new());
+                let winners = self.replay(&order);
+                let last = winners.last()?;
+
+                return Some(order).filter(|_| {
+                    winners.len() == self.game.cards.len()
+                        && last.card == self.game.cards[k].id
+                        && winners.iter().filter(|w| w.call == last.call).count() == 1
+                });
+            },
+        };
+
+        for (i, number) in unhit.iter().enumerate() {
+            if number {
+                let mut next = excluded.clone();
+                next.set(i, true);
+
+                if let Some(order) = self.search_last(k, others, next, seen) {
+                    return Some(order);
                 }
+            }
+        }
 
-                cols = max(cols, (j + 1));
+        None
+    }
+}
+
+impl BingoGame {
+    pub fn solver(&self) -> CallOrderSolver<'_> {
+        CallOrderSolver::new(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RECTANGULAR_GAME: &str = "1,5,2,6\n\n 1  2  3  4\n 5  6  7  8\n\n11 12 13  2\n14 15 16  6\n";
+
+    #[test]
+    fn parses_rectangular_cards() {
+        let game = BingoGame::from_str(RECTANGULAR_GAME).unwrap();
+        let card = &game.cards[0];
+
+        assert_eq!((card.cols, card.rows), (4, 2));
+        assert_eq!(card.get(3, 0), 4);
+        assert_eq!(card.get(0, 1), 5);
+        assert_eq!(card.hslice(1), vec![5, 6, 7, 8]);
+        assert_eq!(card.vslice(3), vec![4, 8]);
+    }
+
+    #[test]
+    fn plays_rectangular_cards() {
+        let result = BingoGame::from_str(RECTANGULAR_GAME).unwrap().play();
+        let winners = result.winners.iter()
+            .map(|w| (w.card, w.number, w.score, w.pattern.name.as_str()))
+            .collect::<Vec<(u32, u32, u64, &str)>>();
+
+        assert_eq!(winners, vec![(0, 5, 150, "column 0"), (1, 6, 486, "column 3")]);
+    }
+
+    #[test]
+    fn shares_places_between_cards_winning_on_the_same_call() {
+        let game = BingoGame::from_str("1,2,3,6,4\n\n1 2\n8 9\n\n7 8\n2 1\n\n3 5\n6 7\n\n10 11\n12 13\n").unwrap();
+        let result = game.play();
+        let winners = result.winners.iter()
+            .map(|w| (w.card, w.call, w.place, w.score))
+            .collect::<Vec<(u32, usize, usize, u64)>>();
+
+        assert_eq!(winners, vec![(0, 1, 1, 34), (1, 1, 1, 30), (2, 3, 3, 72)]);
+        assert_eq!(result.losers.iter().map(|c| c.id).collect::<Vec<u32>>(), vec![3]);
+        assert_eq!(result.remaining_numbers, Vec::<u32>::new());
+    }
+
+    #[test]
+    fn streams_events_in_order() {
+        let game = BingoGame::from_str("5,1,2\n\n1 2\n3 4\n").unwrap();
+        let events = game.run().map(|e| match e {
+            BingoEvent::NumberCalled { number, .. } => format!("call {}", number),
+            BingoEvent::CardMarked { card, x, y } => format!("mark {} {},{}", card, x, y),
+            BingoEvent::CardWon { card, place, .. } => format!("won {} #{}", card, place),
+            BingoEvent::Exhausted { remaining_numbers, losers } => format!("end {:?} {:?}", remaining_numbers, losers),
+        }).collect::<Vec<String>>();
+
+        assert_eq!(events, vec!["call 5", "call 1", "mark 0 0,0", "call 2", "mark 0 1,0", "won 0 #1", "end [] []"]);
+    }
+
+    #[test]
+    fn marks_the_last_column_of_a_wide_card() {
+        let mut card = Bingo::new(vec!["1 2 3 4".to_string(), "5 6 7 8".to_string()], 0).unwrap();
+
+        card.mark(3, 0);
+        card.mark(3, 1);
+
+        assert!(card.is_winning());
+        assert_eq!(card.calculate_solution(8), (1 + 2 + 3 + 5 + 6 + 7) * 8);
+    }
+
+    #[test]
+    #[should_panic]
+    fn rejects_marks_outside_the_card() {
+        Bingo::new(vec!["1 2 3 4".to_string(), "5 6 7 8".to_string()], 0).unwrap().mark(0, 2);
+    }
+
+    #[test]
+    fn rejects_ragged_cards() {
+        assert_eq!(
+            BingoGame::from_str("1\n\n1 2 3\n4 5\n").unwrap_err(),
+            BingoParseError::RaggedCard { card: 0, line: 4, expected: 3, actual: 2 },
+        );
+    }
+
+    #[test]
+    fn rejects_mismatched_card_sizes() {
+        assert_eq!(
+            BingoGame::from_str("1\n\n1 2 3 4\n5 6 7 8\n\n1 2 3\n4 5 6\n").unwrap_err(),
+            BingoParseError::MismatchedCardSize { card: 1, line: 6, expected: (4, 2), actual: (3, 2) },
+        );
+    }
+
+    #[test]
+    fn builds_rectangular_patterns() {
+        assert_eq!(WinRule::FourCorners.patterns(4, 2)[0].cells, vec![Point2::new(0, 0), Point2::new(3, 0), Point2::new(0, 1), Point2::new(3, 1)]);
+        assert_eq!(WinRule::Rows.patterns(4, 2).iter().map(|p| p.cells.len()).collect::<Vec<usize>>(), vec![4, 4]);
+        assert!(WinRule::Diagonals.patterns(4, 2).is_empty());
+    }
+
+    #[test]
+    fn parses_mask_drawings() {
+        let rule = WinRule::mask("plus", ".#.\n###\n.x.");
+
+        assert_eq!(rule, WinRule::Mask("plus".to_string(), vec![
+            Point2::new(1, 0), Point2::new(0, 1), Point2::new(1, 1), Point2::new(2, 1), Point2::new(1, 2),
+        ]));
+        assert_eq!(rule.patterns(3, 3)[0].name, "plus");
+        assert_eq!(rule.patterns(5, 5)[0].cells.len(), 5);
+        assert!(rule.patterns(2, 3).is_empty());
+        assert!(rule.patterns(3, 2).is_empty());
+        assert!(WinRule::mask("nothing", "...\n...").patterns(3, 3).is_empty());
+        assert!(WinRule::XShape.patterns(3, 2).is_empty());
+        assert_eq!(WinRule::XShape.patterns(3, 3)[0].cells.len(), 5);
+    }
+
+    #[test]
+    fn names_the_winning_pattern() {
+        let mut card = Bingo::new(vec!["1 2 3".to_string(), "4 5 6".to_string(), "7 8 9".to_string()], 0).unwrap();
+        let rule = WinRule::AnyOf(vec![WinRule::Rows, WinRule::Diagonals, WinRule::mask("corners", "#.#\n...\n#.#")]);
+
+        for number in [1, 5, 3, 7] {
+            card.mark_number(number);
+        }
+
+        assert_eq!(card.winning_pattern(&rule).map(|p| p.name), Some("anti-diagonal".to_string()));
+        assert_eq!(card.winning_pattern(&WinRule::Rows), None);
+
+        card.mark_number(9);
+
+        assert_eq!(card.winning_pattern(&rule).map(|p| p.name), Some("diagonal".to_string()));
+        assert_eq!(card.winning_pattern(&WinRule::FourCorners).map(|p| p.name), Some("four corners".to_string()));
+
+        card.mark_number(8);
+
+        assert_eq!(card.winning_pattern(&rule).map(|p| p.name), Some("row 2".to_string()));
+    }
+
+    #[test]
+    fn renders_cards() {
+        let mut game = BingoGame::from_str(RECTANGULAR_GAME).unwrap();
+        game.cards[0].mark_number(1);
+        game.cards[0].mark_number(5);
+        game.cards[1].mark_number(12);
+
+        assert_eq!(CardRenderer::default().render(&game.cards[0]), "[1] 2  3  4 \n[5] 6  7  8 ");
+        assert_eq!(game.cards[1].to_string(), " 11 [12] 13   2 \n 14  15  16   6 ");
+        assert_eq!(CardRenderer::ansi().render(&game.cards[0]).lines().next(), Some(" \x1b[1;7m1\x1b[0m  2  3  4 "));
+        assert_eq!(CardRenderer::default().render_board(&game.cards, 2), [
+            "Card 0 *          Card 1",
+            "[ 1]  2   3   4    11 [12] 13   2",
+            "[ 5]  6   7   8    14  15  16   6",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn keeps_board_columns_aligned_with_long_titles() {
+        let mut cards = (8..12).map(|id| Bingo::new(vec!["1".to_string()], id).unwrap()).collect::<Vec<Bingo>>();
+        cards[2].mark_number(1);
+
+        let board = CardRenderer::default().render_board(&cards, 4);
+
+        assert_eq!(board, "#8   #9   #10  #11\n 1    1   [1]   1");
+        assert_eq!(CardRenderer::default().render_board(&cards[3..], 1), "#11\n 1");
+    }
+
+    #[test]
+    fn generates_cards_with_distinct_numbers() {
+        let card = Bingo::random(&mut Rng::new(7), 0, 4, 3, 20);
+        let numbers = card.cells().map(|c| card.get(c.x, c.y)).collect::<HashSet<u32>>();
+
+        assert_eq!((card.cols, card.rows), (4, 3));
+        assert_eq!(numbers.len(), 12);
+        assert!(numbers.iter().all(|n| *n <= 20));
+    }
+
+    #[test]
+    fn reproduces_simulations_from_a_seed() {
+        let config = SimulationConfig { cards: 10, ..SimulationConfig::default() };
+        let report = simulate(&config, 50, 2021);
+
+        assert_eq!(report, simulate(&config, 50, 2021));
+        assert_eq!(report.calls_to_first_win.values().sum::<usize>() + report.no_winner, 50);
+    }
+
+    #[test]
+    fn marks_duplicate_numbers_once_per_cell() {
+        let mut card = Bingo::new(vec!["7 1".to_string(), "2 7".to_string()], 0).unwrap();
+
+        assert_eq!(card.mark_number(7), 2);
+        assert_eq!(card.mark_number(7), 0);
+        assert!(!card.is_winning());
+        assert_eq!(card.marked_cells().collect::<Vec<Point2<usize>>>(), vec![Point2::new(0, 0), Point2::new(1, 1)]);
+    }
+
+    #[test]
+    fn plays_custom_rules_with_the_same_results_as_a_full_scan() {
+        let game = BingoGame::random(&mut Rng::new(99), &SimulationConfig { cards: 200, ..SimulationConfig::default() });
+
+        for rule in [WinRule::standard(), WinRule::XShape, WinRule::AnyOf(vec![WinRule::Diagonals, WinRule::FourCorners])] {
+            let game = game.clone().with_rule(rule.clone());
+
+            for winner in game.play().winners {
+                let mut card = game.cards[winner.card as usize].clone();
+                let called = &game.numbers[..winner.call];
+
+                called.iter().for_each(|n| { card.mark_number(*n); });
+                assert_eq!(card.winning_pattern(&rule), None);
+
+                card.mark_number(winner.number);
+                assert_eq!(card.winning_pattern(&rule), Some(winner.pattern));
+            }
+        }
+    }
+
+    #[test]
+    fn plays_with_the_same_results_as_the_event_stream() {
+        let mut game = BingoGame::random(&mut Rng::new(7), &SimulationConfig { cards: 300, ..SimulationConfig::default() });
+        game.numbers.truncate(40);
+
+        for rule in [WinRule::standard(), WinRule::XShape, WinRule::AnyOf(vec![WinRule::Diagonals, WinRule::FourCorners])] {
+            let game = game.clone().with_rule(rule);
+            let mut run = game.run_with(&WeightedByCall);
+            let mut winners = Vec::new();
+            let mut remaining_numbers = Vec::new();
+
+            for event in run.by_ref() {
+                match event {
+                    BingoEvent::CardWon { card, call, number, score, place, pattern } => {
+                        winners.push(BingoWinner { card, call, number, score, place, pattern });
+                    },
+                    BingoEvent::Exhausted { remaining_numbers: numbers, .. } => remaining_numbers = numbers,
+                    _ => (),
+                }
+            }
+
+            let result = game.play_with(&WeightedByCall);
+            let marks = |cards: Vec<Bingo>| cards.iter().map(|card| (card.id, card.marked_cells().collect())).collect::<Vec<(u32, Vec<_>)>>();
+
+            assert_eq!(result.winners, winners);
+            assert_eq!(result.remaining_numbers, remaining_numbers);
+            assert_eq!(marks(result.losers), marks(run.cards.into_iter().zip(run.won).filter(|(_, won)| !won).map(|(card, _)| card).collect()));
+        }
+
+        let result = game.play();
+
+        assert!(!result.losers.is_empty() && result.remaining_numbers.is_empty());
+    }
+
+    // 100k cards have to be played in well under a second, both settled directly and
+    // through the event stream. Timings only mean something in an optimised build.
+    #[test]
+    #[ignore = "benchmark, run with cargo test --release -- --ignored"]
+    fn plays_100k_cards_quickly() {
+        if cfg!(debug_assertions) {
+            panic!("the 100k card benchmark needs --release");
+        }
+
+        let game = BingoGame::random(&mut Rng::new(2021), &SimulationConfig { cards: 100_000, ..SimulationConfig::default() });
+        let start = std::time::Instant::now();
+        let result = game.play();
+
+        assert!(start.elapsed().as_millis() < 250, "play took {:?}", start.elapsed());
+        assert_eq!(result.winners.len() + result.losers.len(), 100_000);
+
+        let start = std::time::Instant::now();
+        let wins = game.run().filter(|event| matches!(event, BingoEvent::CardWon { .. })).count();
+
+        assert!(start.elapsed().as_millis() < 500, "run took {:?}", start.elapsed());
+        assert_eq!(wins, result.winners.len());
+    }
+
+    #[test]
+    fn finds_the_shortest_first_win() {
+        let game = BingoGame::from_str("1,2,3,4,5\n\n1 2\n3 4\n\n1 3\n5 2\n").unwrap();
+        let solver = game.solver();
+
+        assert_eq!(solver.shortest_first_win(0), Some(vec![1, 2]));
+        assert_eq!(solver.shortest_first_win(1), Some(vec![2, 5]));
+    }
+
+    #[test]
+    fn proves_a_first_win_impossible() {
+        let game = BingoGame::from_str("1,2,3,4\n\n1 2\n3 4\n\n1 2\n3 4\n").unwrap();
+
+        assert_eq!(game.solver().shortest_first_win(0), None);
+        assert_eq!(game.solver().last_win(0), None);
+    }
+
+    #[test]
+    fn finds_an_order_where_a_card_wins_last() {
+        let game = BingoGame::from_str(RECTANGULAR_GAME).unwrap();
+        let order = game.solver().last_win(1).unwrap();
+        let winners = BingoGame { numbers: order, ..game.clone() }.play().winners;
+
+        assert_eq!(winners.iter().map(|w| w.card).collect::<Vec<u32>>(), vec![0, 1]);
+        assert!(winners[0].call < winners[1].call);
+    }
+
+    #[test]
+    fn proves_a_last_win_impossible() {
+        // Card 0 can only win with 1 and 2, which also completes the top row of card 1.
+        let game = BingoGame::from_str("1,2,5,6\n\n1 2\n1 2\n\n1 2\n5 6\n").unwrap()
+            .with_rule(WinRule::Rows);
+
+        assert_eq!(game.solver().shortest_first_win(0), None);
+        assert_eq!(game.solver().last_win(1), None);
+        assert_eq!(game.solver().last_win(0), Some(vec![5, 6, 1, 2]));
+    }
+
+    #[test]
+    fn records_scores_from_the_selected_strategy() {
+        let game = BingoGame::from_str(RECTANGULAR_GAME).unwrap();
+        let scores = |scoring: &dyn ScoringStrategy| {
+            game.play_with(scoring).winners.iter().map(|w| w.score).collect::<Vec<u64>>()
+        };
+
+        assert_eq!(scores(&StandardScoring), vec![150, 486]);
+        assert_eq!(scores(&SumOfMarked), vec![6, 8]);
+        assert_eq!(scores(&WinningLineProduct), vec![5, 12]);
+        assert_eq!(scores(&WeightedByCall), vec![300, 1944]);
+        assert_eq!(scores(&|card: &Bingo, _: &Pattern, _: u32, _: usize| card.id as u64), vec![0, 1]);
+    }
 
-                &data[i].push(number.parse::<u32>().unwrap());
-            })
-        });
+    #[test]
+    fn saturates_scores_instead_of_overflowing() {
+        let game = BingoGame::from_str("4000000000\n\n4000000000 4000000000\n4000000001 4000000002\n").unwrap();
+        let score = |scoring: &dyn ScoringStrategy| game.play_with(scoring).winners[0].score;
 
-        Bingo{ id, rows, cols, data, markers: HashSet::new() }
+        assert_eq!(score(&StandardScoring), u64::MAX);
+        assert_eq!(score(&WeightedByCall), u64::MAX);
+        assert_eq!(score(&WinningLineProduct), 16_000_000_000_000_000_000);
+        assert_eq!(score(&SumOfMarked), 8_000_000_000);
     }
 }
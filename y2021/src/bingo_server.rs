@@ -0,0 +1,533 @@
+use std::fmt::{Display, Formatter};
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread;
+use super::bingo::{BingoGame, BingoWinner, ScoringStrategy, StandardScoring};
+
+#[derive(PartialEq, Debug)]
+pub enum MessageParseError {
+    Empty,
+    UnknownCommand(String),
+    MissingArgument(String),
+    InvalidArgument(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ClientMessage {
+    Join(String),
+    Claim,
+    Quit,
+}
+
+impl FromStr for ClientMessage {
+    type Err = MessageParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (command, rest) = split_command(s)?;
+
+        match command {
+            "JOIN" if !rest.is_empty() => Ok(ClientMessage::Join(rest.to_string())),
+            "JOIN" => Err(MessageParseError::MissingArgument("name".to_string())),
+            "CLAIM" => Ok(ClientMessage::Claim),
+            "QUIT" => Ok(ClientMessage::Quit),
+            c => Err(MessageParseError::UnknownCommand(c.to_string())),
+        }
+    }
+}
+
+impl Display for ClientMessage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ClientMessage::Join(name) => write!(f, "JOIN {}", name),
+            ClientMessage::Claim => write!(f, "CLAIM"),
+            ClientMessage::Quit => write!(f, "QUIT"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ServerMessage {
+    Card { card: u32, cols: usize, rows: usize, numbers: Vec<u32> },
+    Call { call: usize, number: u32 },
+    Verified { card: u32, score: u64, pattern: String },
+    Rejected(String),
+    Winner { card: u32, name: String },
+    End,
+    Error(String),
+}
+
+impl FromStr for ServerMessage {
+    type Err = MessageParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (command, rest) = split_command(s)?;
+        let mut args = rest.splitn(4, ' ');
+        let mut next = |name: &str| args.next().filter(|a| !a.is_empty())
+            .ok_or_else(|| MessageParseError::MissingArgument(name.to_string()));
+
+        match command {
+            "CARD" => Ok(ServerMessage::Card {
+                card: parse_argument(next("card")?)?,
+                cols: parse_argument(next("cols")?)?,
+                rows: parse_argument(next("rows")?)?,
+                numbers: next("numbers")?.split(',').map(parse_argument).collect::<Result<Vec<u32>, MessageParseError>>()?,
+            }),
+            "CALL" => Ok(ServerMessage::Call { call: parse_argument(next("call")?)?, number: parse_argument(next("number")?)? }),
+            "VERIFIED" => {
+                let card = parse_argument(next("card")?)?;
+                let score = parse_argument(next("score")?)?;
+                let pattern = rest.splitn(3, ' ').nth(2).unwrap_or("").to_string();
+
+                Ok(ServerMessage::Verified { card, score, pattern })
+            },
+            "REJECTED" => Ok(ServerMessage::Rejected(rest.to_string())),
+            "WINNER" => Ok(ServerMessage::Winner {
+                card: parse_argument(next("card")?)?,
+                name: rest.split_once(' ').map(|(_, name)| name.to_string()).unwrap_or_default(),
+            }),
+            "END" => Ok(ServerMessage::End),
+            "ERROR" => Ok(ServerMessage::Error(rest.to_string())),
+            c => Err(MessageParseError::UnknownCommand(c.to_string())),
+        }
+    }
+}
+
+impl Display for ServerMessage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ServerMessage::Card { card, cols, rows, numbers } => write!(
+                f, "CARD {} {} {} {}", card, cols, rows,
+                numbers.iter().map(|n| n.to_string()).collect::<Vec<String>>().join(","),
+            ),
+            ServerMessage::Call { call, number } => write!(f, "CALL {} {}", call, number),
+            ServerMessage::Verified { card, score, pattern } => write!(f, "VERIFIED {} {} {}", card, score, pattern),
+            ServerMessage::Rejected(reason) => write!(f, "REJECTED {}", reason),
+            ServerMessage::Winner { card, name } => write!(f, "WINNER {} {}", card, name),
+            ServerMessage::End => write!(f, "END"),
+            ServerMessage::Error(reason) => write!(f, "ERROR {}", reason),
+        }
+    }
+}
+
+fn split_command(s: &str) -> Result<(&str, &str), MessageParseError> {
+    match s.trim() {
+        "" => Err(MessageParseError::Empty),
+        line => Ok(line.split_once(' ').map(|(c, rest)| (c, rest.trim())).unwrap_or((line, ""))),
+    }
+}
+
+fn parse_argument<T: FromStr>(s: &str) -> Result<T, MessageParseError> {
+    s.parse::<T>().map_err(|_| MessageParseError::InvalidArgument(s.to_string()))
+}
+
+// `win` is filled in by the call that first completes the player's card, so a claim
+// scores the same however late it arrives. Its place is only settled by the claim.
+struct Player {
+    name: String,
+    card: usize,
+    stream: Option<TcpStream>,
+    win: Option<BingoWinner>,
+}
+
+struct ServerState {
+    game: BingoGame,
+    players: Vec<Player>,
+    winners: Vec<BingoWinner>,
+    call: usize,
+    ended: bool,
+}
+
+impl ServerState {
+    fn send(stream: &mut Option<TcpStream>, message: &ServerMessage) {
+        let failed = stream.as_mut().map(|s| writeln!(s, "{}", message).is_err()).unwrap_or(false);
+
+        if failed {
+            *stream = None;
+        }
+    }
+
+    fn broadcast(&mut self, message: &ServerMessage) {
+        for player in self.players.iter_mut() {
+            ServerState::send(&mut player.stream, message);
+        }
+    }
+
+    fn join(&mut self, name: String, stream: TcpStream) -> Result<usize, ServerMessage> {
+        let card = self.players.len();
+
+        if card >= self.game.cards.len() {
+            return Err(ServerMessage::Error("no cards left".to_string()));
+        }
+
+        let assigned = &self.game.cards[card];
+        let mut stream = Some(stream);
+
+        ServerState::send(&mut stream, &ServerMessage::Card {
+            card: assigned.id,
+            cols: assigned.cols,
+            rows: assigned.rows,
+            numbers: assigned.cells().map(|c| assigned.get(c.x, c.y)).collect(),
+        });
+
+        let mut player = Player { name, card, stream, win: None };
+
+        // A late joiner catches up on the numbers already called, both on the card
+        // and on the wire, so a win among them can still be claimed.
+        for call in 0..self.call {
+            ServerState::mark(&mut self.game, &mut player, call);
+            ServerState::send(&mut player.stream, &ServerMessage::Call { call, number: self.game.numbers[call] });
+        }
+
+        if self.ended {
+            ServerState::send(&mut player.stream, &ServerMessage::End);
+        }
+
+        self.players.push(player);
+
+        Ok(card)
+    }
+
+    // Marks a call on the player's card, recording the win if it completes one.
+    fn mark(game: &mut BingoGame, player: &mut Player, call: usize) {
+        let number = game.numbers[call];
+        let card = &mut game.cards[player.card];
+
+        if player.win.is_some() || card.mark_number(number) == 0 {
+            return;
+        }
+
+        player.win = card.winning_pattern(&game.rule).map(|pattern| BingoWinner {
+            card: card.id,
+            call,
+            number,
+            score: StandardScoring.score(card, &pattern, number, call),
+            place: 0,
+            pattern,
+        });
+    }
+
+    fn claim(&mut self, player: usize) -> ServerMessage {
+        let card = &self.game.cards[self.players[player].card];
+
+        if self.winners.iter().any(|w| w.card == card.id) {
+            return ServerMessage::Rejected("card has already won".to_string());
+        }
+
+        if self.call == 0 {
+            return ServerMessage::Rejected("no numbers have been called".to_string());
+        }
+
+        match self.players[player].win.clone() {
+            Some(mut winner) => {
+                winner.place = self.winners.len() + 1;
+
+                let verified = ServerMessage::Verified { card: winner.card, score: winner.score, pattern: winner.pattern.name.clone() };
+                let announcement = ServerMessage::Winner { card: winner.card, name: self.players[player].name.clone() };
+
+                self.winners.push(winner);
+
+                for (i, other) in self.players.iter_mut().enumerate() {
+                    if i != player {
+                        ServerState::send(&mut other.stream, &announcement);
+                    }
+                }
+
+                verified
+            },
+            None => ServerMessage::Rejected("card is not winning".to_string()),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct ServerHandle {
+    state: Arc<Mutex<ServerState>>,
+}
+
+impl ServerHandle {
+    fn lock(&self) -> MutexGuard<'_, ServerState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn call_next(&self) -> Option<u32> {
+        let mut state = self.lock();
+
+        if state.call >= state.game.numbers.len() {
+            if !state.ended {
+                state.ended = true;
+                state.broadcast(&ServerMessage::End);
+            }
+
+            return None;
+        }
+
+        let call = state.call;
+        let number = state.game.numbers[call];
+        let ServerState { game, players, .. } = &mut *state;
+
+        for player in players.iter_mut() {
+            ServerState::mark(game, player, call);
+        }
+
+        state.call += 1;
+        state.broadcast(&ServerMessage::Call { call, number });
+
+        Some(number)
+    }
+
+    pub fn player_count(&self) -> usize {
+        self.lock().players.len()
+    }
+
+    pub fn winners(&self) -> Vec<BingoWinner> {
+        self.lock().winners.clone()
+    }
+
+    fn handle_connection(&self, stream: TcpStream) {
+        let mut writer = match stream.try_clone() {
+            Ok(writer) => writer,
+            Err(_) => return,
+        };
+        let mut player: Option<usize> = None;
+
+        for line in BufReader::new(stream).lines() {
+            let line = match line {
+                Ok(line) => line,
+                Err(_) => break,
+            };
+
+            let reply = match (ClientMessage::from_str(line.as_str()), player) {
+                (Ok(ClientMessage::Join(_)), Some(_)) => Some(ServerMessage::Error("already joined".to_string())),
+                (Ok(ClientMessage::Join(name)), None) => match writer.try_clone() {
+                    Ok(stream) => match self.lock().join(name, stream) {
+                        Ok(p) => {
+                            player = Some(p);
+                            None
+                        },
+                        Err(e) => Some(e),
+                    },
+                    Err(_) => break,
+                },
+                (Ok(ClientMessage::Claim), Some(p)) => Some(self.lock().claim(p)),
+                (Ok(ClientMessage::Claim), None) => Some(ServerMessage::Error("join before claiming".to_string())),
+                (Ok(ClientMessage::Quit), _) => break,
+                (Err(e), _) => Some(ServerMessage::Error(format!("{:?}", e))),
+            };
+
+            if let Some(reply) = reply {
+                // Replies go through the state lock so they never interleave with a broadcast.
+                let _state = self.lock();
+
+                if writeln!(writer, "{}", reply).is_err() {
+                    break;
+                }
+            }
+        }
+
+        if let Some(p) = player {
+            self.lock().players[p].stream = None;
+        }
+    }
+}
+
+pub struct BingoServer {
+    listener: TcpListener,
+    handle: ServerHandle,
+}
+
+impl BingoServer {
+    pub fn bind<A: ToSocketAddrs>(addr: A, game: BingoGame) -> std::io::Result<BingoServer> {
+        Ok(BingoServer {
+            listener: TcpListener::bind(addr)?,
+            handle: ServerHandle {
+                state: Arc::new(Mutex::new(ServerState { game, players: Vec::new(), winners: Vec::new(), call: 0, ended: false })),
+            },
+        })
+    }
+
+    pub fn local_addr(&self) -> std::io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    pub fn handle(&self) -> ServerHandle {
+        self.handle.clone()
+    }
+
+    pub fn serve(self) {
+        for stream in self.listener.incoming().flatten() {
+            let handle = self.handle.clone();
+
+            thread::spawn(move || handle.handle_connection(stream));
+        }
+    }
+
+    pub fn spawn(self) -> ServerHandle {
+        let handle = self.handle();
+
+        thread::spawn(move || self.serve());
+
+        handle
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    const GAME: &str = "1,2,3,4\n\n1 2\n3 4\n\n4 3\n2 1\n";
+
+    struct Client {
+        reader: BufReader<TcpStream>,
+        writer: TcpStream,
+    }
+
+    impl Client {
+        fn connect(addr: SocketAddr) -> Client {
+            let stream = TcpStream::connect(addr).unwrap();
+            stream.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+
+            Client { writer: stream.try_clone().unwrap(), reader: BufReader::new(stream) }
+        }
+
+        fn send(&mut self, message: ClientMessage) {
+            writeln!(self.writer, "{}", message).unwrap();
+        }
+
+        fn receive(&mut self) -> ServerMessage {
+            let mut line = String::new();
+            self.reader.read_line(&mut line).unwrap();
+
+            ServerMessage::from_str(line.as_str()).unwrap()
+        }
+    }
+
+    fn start() -> (SocketAddr, ServerHandle) {
+        let server = BingoServer::bind("127.0.0.1:0", BingoGame::from_str(GAME).unwrap()).unwrap();
+        let addr = server.local_addr().unwrap();
+
+        (addr, server.spawn())
+    }
+
+    #[test]
+    fn round_trips_messages() {
+        for message in [
+            ServerMessage::Card { card: 1, cols: 2, rows: 2, numbers: vec![4, 3, 2, 1] },
+            ServerMessage::Verified { card: 0, score: 14, pattern: "row 0".to_string() },
+            ServerMessage::Winner { card: 3, name: "Ada Lovelace".to_string() },
+            ServerMessage::Rejected("card is not winning".to_string()),
+        ] {
+            assert_eq!(ServerMessage::from_str(message.to_string().as_str()), Ok(message));
+        }
+
+        assert_eq!(ClientMessage::from_str("JOIN Ada"), Ok(ClientMessage::Join("Ada".to_string())));
+        assert_eq!(ClientMessage::from_str("DANCE"), Err(MessageParseError::UnknownCommand("DANCE".to_string())));
+    }
+
+    #[test]
+    fn plays_a_game_over_loopback() {
+        let (addr, handle) = start();
+        let mut alice = Client::connect(addr);
+        let mut bob = Client::connect(addr);
+
+        alice.send(ClientMessage::Join("alice".to_string()));
+        assert_eq!(alice.receive(), ServerMessage::Card { card: 0, cols: 2, rows: 2, numbers: vec![1, 2, 3, 4] });
+
+        bob.send(ClientMessage::Join("bob".to_string()));
+        assert_eq!(bob.receive(), ServerMessage::Card { card: 1, cols: 2, rows: 2, numbers: vec![4, 3, 2, 1] });
+
+        alice.send(ClientMessage::Claim);
+        assert_eq!(alice.receive(), ServerMessage::Rejected("no numbers have been called".to_string()));
+
+        assert_eq!(handle.call_next(), Some(1));
+        assert_eq!(alice.receive(), ServerMessage::Call { call: 0, number: 1 });
+        assert_eq!(bob.receive(), ServerMessage::Call { call: 0, number: 1 });
+
+        alice.send(ClientMessage::Claim);
+        assert_eq!(alice.receive(), ServerMessage::Rejected("card is not winning".to_string()));
+
+        assert_eq!(handle.call_next(), Some(2));
+        assert_eq!(alice.receive(), ServerMessage::Call { call: 1, number: 2 });
+        assert_eq!(bob.receive(), ServerMessage::Call { call: 1, number: 2 });
+
+        alice.send(ClientMessage::Claim);
+        assert_eq!(alice.receive(), ServerMessage::Verified { card: 0, score: 14, pattern: "row 0".to_string() });
+        assert_eq!(bob.receive(), ServerMessage::Winner { card: 0, name: "alice".to_string() });
+
+        alice.send(ClientMessage::Claim);
+        assert_eq!(alice.receive(), ServerMessage::Rejected("card has already won".to_string()));
+
+        assert_eq!(handle.winners().len(), 1);
+    }
+
+    #[test]
+    fn scores_late_claims_from_the_winning_call() {
+        let (addr, handle) = start();
+        let mut alice = Client::connect(addr);
+
+        alice.send(ClientMessage::Join("alice".to_string()));
+        alice.receive();
+
+        for call in 0..3 {
+            handle.call_next();
+            assert!(matches!(alice.receive(), ServerMessage::Call { call: c, .. } if c == call));
+        }
+
+        alice.send(ClientMessage::Claim);
+        assert_eq!(alice.receive(), ServerMessage::Verified { card: 0, score: 14, pattern: "row 0".to_string() });
+
+        let winners = handle.winners();
+        let expected = &BingoGame::from_str(GAME).unwrap().play().winners[0];
+
+        assert_eq!((winners[0].call, winners[0].number, winners[0].score), (expected.call, expected.number, expected.score));
+        assert_eq!(winners[0].place, 1);
+    }
+
+    #[test]
+    fn catches_up_players_joining_mid_game() {
+        let (addr, handle) = start();
+        let mut alice = Client::connect(addr);
+        let mut bob = Client::connect(addr);
+
+        alice.send(ClientMessage::Join("alice".to_string()));
+        alice.receive();
+
+        assert_eq!(handle.call_next(), Some(1));
+        assert_eq!(handle.call_next(), Some(2));
+
+        bob.send(ClientMessage::Join("bob".to_string()));
+        assert_eq!(bob.receive(), ServerMessage::Card { card: 1, cols: 2, rows: 2, numbers: vec![4, 3, 2, 1] });
+        assert_eq!(bob.receive(), ServerMessage::Call { call: 0, number: 1 });
+        assert_eq!(bob.receive(), ServerMessage::Call { call: 1, number: 2 });
+
+        bob.send(ClientMessage::Claim);
+        assert_eq!(bob.receive(), ServerMessage::Verified { card: 1, score: 14, pattern: "row 1".to_string() });
+        assert_eq!(handle.winners()[0].call, 1);
+    }
+
+    #[test]
+    fn rejects_players_when_cards_run_out() {
+        let (addr, handle) = start();
+        let mut clients = (0..3).map(|_| Client::connect(addr)).collect::<Vec<Client>>();
+
+        let replies = clients.iter_mut().enumerate().map(|(i, client)| {
+            client.send(ClientMessage::Join(format!("player {}", i)));
+            client.receive()
+        }).collect::<Vec<ServerMessage>>();
+
+        assert!(matches!(replies[0], ServerMessage::Card { card: 0, .. }));
+        assert!(matches!(replies[1], ServerMessage::Card { card: 1, .. }));
+        assert_eq!(replies[2], ServerMessage::Error("no cards left".to_string()));
+        assert_eq!(handle.player_count(), 2);
+
+        while handle.call_next().is_some() {}
+
+        for _ in 0..4 {
+            assert!(matches!(clients[0].receive(), ServerMessage::Call { .. }));
+        }
+
+        assert_eq!(clients[0].receive(), ServerMessage::End);
+    }
+}
@@ -0,0 +1,336 @@
+use std::fmt::{Display, Formatter};
+use std::iter::FromIterator;
+use std::ops::{BitAnd, BitOr, BitXor, Not};
+use std::str::FromStr;
+
+const WORD_BITS: usize = 64;
+
+#[derive(PartialEq, Debug)]
+pub enum BitStringParseError {
+    InvalidBinaryDigit(usize, char),
+    InvalidHexDigit(usize, char),
+}
+
+#[derive(PartialEq, Debug)]
+pub enum BitReadError {
+    WidthTooLarge(usize),
+    OutOfBounds { offset: usize, width: usize, len: usize },
+}
+
+// Bits are stored most significant first: bit 0 is the leftmost character of the
+// text the string was parsed from.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct BitString {
+    len: usize,
+    words: Vec<u64>,
+}
+
+impl BitString {
+    pub fn new() -> BitString {
+        BitString { len: 0, words: Vec::new() }
+    }
+
+    pub fn zeros(len: usize) -> BitString {
+        BitString { len, words: vec![0; len.div_ceil(WORD_BITS)] }
+    }
+
+    pub fn from_binary(s: &str) -> Result<BitString, BitStringParseError> {
+        let mut result = BitString::new();
+
+        for (i, c) in s.trim().chars().enumerate() {
+            match c {
+                '0' => result.push(false),
+                '1' => result.push(true),
+                c => return Err(BitStringParseError::InvalidBinaryDigit(i, c)),
+            }
+        }
+
+        Ok(result)
+    }
+
+    pub fn from_hex(s: &str) -> Result<BitString, BitStringParseError> {
+        let mut result = BitString::new();
+
+        for (i, c) in s.trim().chars().enumerate() {
+            let nibble = c.to_digit(16).ok_or(BitStringParseError::InvalidHexDigit(i, c))?;
+            result.push_bits(nibble as u64, 4);
+        }
+
+        Ok(result)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, index: usize) -> Option<bool> {
+        if index >= self.len {
+            return None;
+        }
+
+        Some(self.words[index / WORD_BITS] & Self::mask(index) != 0)
+    }
+
+    pub fn set(&mut self, index: usize, value: bool) {
+        assert!(index < self.len);
+
+        if value {
+            self.words[index / WORD_BITS] |= Self::mask(index);
+        } else {
+            self.words[index / WORD_BITS] &= !Self::mask(index);
+        }
+    }
+
+    pub fn push(&mut self, value: bool) {
+        if self.len.is_multiple_of(WORD_BITS) {
+            self.words.push(0);
+        }
+
+        self.len += 1;
+        self.set(self.len - 1, value);
+    }
+
+    pub fn push_bits(&mut self, value: u64, width: usize) {
+        assert!(width <= WORD_BITS);
+
+        for i in (0..width).rev() {
+            self.push((value >> i) & 1 == 1);
+        }
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    pub fn read_u64(&self, offset: usize, width: usize) -> Result<u64, BitReadError> {
+        if width > 64 {
+            return Err(BitReadError::WidthTooLarge(width));
+        }
+
+        self.read_u128(offset, width).map(|v| v as u64)
+    }
+
+    pub fn read_u128(&self, offset: usize, width: usize) -> Result<u128, BitReadError> {
+        if width > 128 {
+            return Err(BitReadError::WidthTooLarge(width));
+        }
+
+        if offset + width > self.len {
+            return Err(BitReadError::OutOfBounds { offset, width, len: self.len });
+        }
+
+        let mut result: u128 = 0;
+        let mut index = offset;
+        let end = offset + width;
+
+        while index < end {
+            let bit = index % WORD_BITS;
+            let take = (WORD_BITS - bit).min(end - index);
+            let chunk = (self.words[index / WORD_BITS] << bit) >> (WORD_BITS - take);
+
+            result = (result << take) | chunk as u128;
+            index += take;
+        }
+
+        Ok(result)
+    }
+
+    pub fn to_u64(&self) -> Result<u64, BitReadError> {
+        self.read_u64(0, self.len)
+    }
+
+    pub fn to_u128(&self) -> Result<u128, BitReadError> {
+        self.read_u128(0, self.len)
+    }
+
+    pub fn cursor(&self) -> BitCursor<'_> {
+        BitCursor { bits: self, position: 0 }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = bool> + '_ {
+        (0..self.len).map(move |i| self.words[i / WORD_BITS] & Self::mask(i) != 0)
+    }
+
+    fn mask(index: usize) -> u64 {
+        1 << (WORD_BITS - 1 - index % WORD_BITS)
+    }
+
+    fn clear_padding(&mut self) {
+        let used = self.len % WORD_BITS;
+
+        if used != 0 {
+            if let Some(last) = self.words.last_mut() {
+                *last &= !(u64::MAX >> used);
+            }
+        }
+    }
+
+    fn zip_words(&self, other: &BitString, f: fn(u64, u64) -> u64) -> BitString {
+        assert_eq!(self.len, other.len, "bitwise operands must have the same width");
+
+        BitString {
+            len: self.len,
+            words: self.words.iter().zip(other.words.iter()).map(|(a, b)| f(*a, *b)).collect(),
+        }
+    }
+}
+
+impl FromStr for BitString {
+    type Err = BitStringParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        BitString::from_binary(s)
+    }
+}
+
+impl FromIterator<bool> for BitString {
+    fn from_iter<I: IntoIterator<Item = bool>>(iter: I) -> Self {
+        let mut result = BitString::new();
+
+        for bit in iter {
+            result.push(bit);
+        }
+
+        result
+    }
+}
+
+impl Display for BitString {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for bit in self.iter() {
+            write!(f, "{}", if bit { '1' } else { '0' })?;
+        }
+
+        Ok(())
+    }
+}
+
+impl BitAnd for &BitString {
+    type Output = BitString;
+
+    fn bitand(self, rhs: Self) -> BitString {
+        self.zip_words(rhs, |a, b| a & b)
+    }
+}
+
+impl BitOr for &BitString {
+    type Output = BitString;
+
+    fn bitor(self, rhs: Self) -> BitString {
+        self.zip_words(rhs, |a, b| a | b)
+    }
+}
+
+impl BitXor for &BitString {
+    type Output = BitString;
+
+    fn bitxor(self, rhs: Self) -> BitString {
+        self.zip_words(rhs, |a, b| a ^ b)
+    }
+}
+
+impl Not for &BitString {
+    type Output = BitString;
+
+    fn not(self) -> BitString {
+        let mut result = BitString {
+            len: self.len,
+            words: self.words.iter().map(|w| !w).collect(),
+        };
+
+        result.clear_padding();
+        result
+    }
+}
+
+pub struct BitCursor<'a> {
+    bits: &'a BitString,
+    pub position: usize,
+}
+
+impl<'a> BitCursor<'a> {
+    pub fn remaining(&self) -> usize {
+        self.bits.len() - self.position
+    }
+
+    pub fn read_bit(&mut self) -> Result<bool, BitReadError> {
+        self.read_u64(1).map(|v| v == 1)
+    }
+
+    pub fn read_u64(&mut self, width: usize) -> Result<u64, BitReadError> {
+        let value = self.bits.read_u64(self.position, width)?;
+        self.position += width;
+
+        Ok(value)
+    }
+
+    pub fn read_u128(&mut self, width: usize) -> Result<u128, BitReadError> {
+        let value = self.bits.read_u128(self.position, width)?;
+        self.position += width;
+
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_across_word_boundaries() {
+        let mut bits = BitString::zeros(60);
+        bits.push_bits(0b1011_0110, 8);
+        bits.push_bits(u64::MAX, 64);
+
+        assert_eq!(bits.len(), 132);
+        assert_eq!(bits.read_u64(60, 8), Ok(0b1011_0110));
+        assert_eq!(bits.read_u64(62, 6), Ok(0b11_0110));
+        assert_eq!(bits.read_u64(68, 64), Ok(u64::MAX));
+        assert_eq!(bits.read_u128(4, 128), Ok(((0b1011_0110_u128) << 64) | u64::MAX as u128));
+        assert_eq!(bits.read_u128(0, 0), Ok(0));
+    }
+
+    #[test]
+    fn reports_bad_reads() {
+        let bits = BitString::from_binary("101").unwrap();
+
+        assert_eq!(bits.read_u64(0, 65), Err(BitReadError::WidthTooLarge(65)));
+        assert_eq!(bits.read_u128(0, 129), Err(BitReadError::WidthTooLarge(129)));
+        assert_eq!(bits.read_u64(2, 2), Err(BitReadError::OutOfBounds { offset: 2, width: 2, len: 3 }));
+
+        let mut cursor = bits.cursor();
+
+        assert_eq!(cursor.read_u64(2), Ok(0b10));
+        assert_eq!(cursor.remaining(), 1);
+        assert_eq!(cursor.read_u64(2), Err(BitReadError::OutOfBounds { offset: 2, width: 2, len: 3 }));
+        assert_eq!(cursor.position, 2);
+    }
+
+    #[test]
+    fn keeps_padding_clear_when_negating() {
+        let bits = BitString::from_binary(&"10".repeat(35)).unwrap();
+        let inverted = !&bits;
+
+        assert_eq!(inverted.len(), 70);
+        assert_eq!(inverted.count_ones(), 35);
+        assert_eq!(inverted.to_string(), "01".repeat(35));
+        assert_eq!((&bits | &inverted).count_ones(), 70);
+        assert_eq!((&bits & &inverted).count_ones(), 0);
+        assert_eq!((&bits ^ &bits).count_ones(), 0);
+        assert_eq!(!&inverted, bits);
+    }
+
+    #[test]
+    fn parses_binary_and_hex() {
+        assert_eq!(BitString::from_hex("D2FE28").unwrap().to_string(), "110100101111111000101000");
+        assert_eq!("00101".parse::<BitString>().unwrap().to_u64(), Ok(5));
+        assert_eq!(BitString::from_binary("0120"), Err(BitStringParseError::InvalidBinaryDigit(2, '2')));
+        assert_eq!(BitString::from_hex("8AZ"), Err(BitStringParseError::InvalidHexDigit(2, 'Z')));
+        assert_eq!([true, false, true].into_iter().collect::<BitString>().to_string(), "101");
+        assert!(BitString::new().is_empty());
+    }
+}
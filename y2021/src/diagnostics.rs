@@ -0,0 +1,195 @@
+use std::io::BufRead;
+use super::bit_string::{BitString, BitStringParseError};
+
+const MAX_WIDTH: usize = 64;
+
+#[derive(Debug)]
+pub enum DiagnosticError {
+    Io(std::io::Error),
+    InvalidLine(usize, BitStringParseError),
+    InconsistentWidth { line: usize, expected: usize, actual: usize },
+    WidthTooLarge { line: usize, width: usize },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct PowerConsumption {
+    pub width: usize,
+    pub lines: usize,
+    pub gamma: u64,
+    pub epsilon: u64,
+    pub ties: u64,
+}
+
+impl PowerConsumption {
+    // Both rates can be 64 bits wide, so their product needs 128.
+    pub fn power(&self) -> u128 {
+        self.gamma as u128 * self.epsilon as u128
+    }
+
+    pub fn is_tied(&self, column: usize) -> bool {
+        assert!(column < self.width);
+
+        self.ties >> (self.width - 1 - column) & 1 == 1
+    }
+}
+
+// Counts the ones in every column of the report at once. Each plane holds one bit
+// of all 64 column counters, so adding a line is a ripple-carry over the planes
+// rather than a loop over the columns.
+#[derive(Clone, Debug, Default)]
+pub struct ColumnCounter {
+    pub width: Option<usize>,
+    pub lines: usize,
+    planes: Vec<u64>,
+}
+
+impl ColumnCounter {
+    pub fn new() -> ColumnCounter {
+        ColumnCounter { width: None, lines: 0, planes: Vec::new() }
+    }
+
+    pub fn add(&mut self, word: u64) {
+        let mut carry = word;
+
+        for plane in self.planes.iter_mut() {
+            if carry == 0 {
+                break;
+            }
+
+            let sum = *plane ^ carry;
+            carry &= *plane;
+            *plane = sum;
+        }
+
+        if carry != 0 {
+            self.planes.push(carry);
+        }
+
+        self.lines += 1;
+    }
+
+    pub fn add_line(&mut self, line: &str) -> Result<(), DiagnosticError> {
+        self.add_numbered_line(self.lines + 1, line)
+    }
+
+    fn add_numbered_line(&mut self, number: usize, line: &str) -> Result<(), DiagnosticError> {
+        let bits = BitString::from_binary(line).map_err(|e| DiagnosticError::InvalidLine(number, e))?;
+
+        if bits.len() > MAX_WIDTH {
+            return Err(DiagnosticError::WidthTooLarge { line: number, width: bits.len() });
+        }
+
+        match self.width {
+            Some(expected) if expected != bits.len() => {
+                return Err(DiagnosticError::InconsistentWidth { line: number, expected, actual: bits.len() });
+            },
+            Some(_) => (),
+            None => self.width = Some(bits.len()),
+        }
+
+        self.add(bits.to_u64().unwrap_or(0));
+
+        Ok(())
+    }
+
+    pub fn ones(&self, bit: usize) -> u64 {
+        self.planes.iter().enumerate()
+            .map(|(k, plane)| (plane >> bit & 1) << k)
+            .sum()
+    }
+
+    pub fn finish(&self) -> PowerConsumption {
+        let width = self.width.unwrap_or(0);
+        let mut result = PowerConsumption { width, lines: self.lines, gamma: 0, epsilon: 0, ties: 0 };
+
+        for bit in 0..width {
+            let ones = self.ones(bit) as usize;
+            let zeros = self.lines - ones;
+
+            if ones >= zeros {
+                result.gamma |= 1 << bit;
+            } else {
+                result.epsilon |= 1 << bit;
+            }
+
+            if ones == zeros {
+                result.ties |= 1 << bit;
+            }
+        }
+
+        result
+    }
+}
+
+pub fn power_consumption<R: BufRead>(reader: R) -> Result<PowerConsumption, DiagnosticError> {
+    let mut counter = ColumnCounter::new();
+
+    for (i, line) in reader.lines().enumerate() {
+        let line = line.map_err(DiagnosticError::Io)?;
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        counter.add_numbered_line(i + 1, line.as_str())?;
+    }
+
+    Ok(counter.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = "00100\n11110\n10110\n10111\n10101\n01111\n00111\n11100\n10000\n11001\n00010\n01010\n";
+
+    #[test]
+    fn computes_the_example() {
+        let result = power_consumption(EXAMPLE.as_bytes()).unwrap();
+
+        assert_eq!((result.width, result.lines, result.gamma, result.epsilon), (5, 12, 22, 9));
+        assert_eq!(result.power(), 198);
+        assert_eq!(result.ties, 0);
+    }
+
+    #[test]
+    fn reports_ties() {
+        let result = power_consumption("10\n01\n11\n10\n".as_bytes()).unwrap();
+
+        assert!(!result.is_tied(0));
+        assert!(result.is_tied(1));
+        assert_eq!((result.gamma, result.epsilon), (0b11, 0b00));
+    }
+
+    #[test]
+    fn handles_64_bit_reports() {
+        let line = format!("{}{}", "1".repeat(32), "0".repeat(32));
+        let result = power_consumption(format!("{}\n{}\n", line, line).as_bytes()).unwrap();
+
+        assert_eq!(result.width, 64);
+        assert_eq!(result.gamma, 0xFFFF_FFFF_0000_0000);
+        assert_eq!(result.epsilon, 0xFFFF_FFFF);
+        assert_eq!(result.power(), 0xFFFF_FFFF_0000_0000_u128 * 0xFFFF_FFFF);
+    }
+
+    #[test]
+    fn rejects_bad_lines() {
+        assert!(matches!(
+            power_consumption("101\n\n1101\n".as_bytes()),
+            Err(DiagnosticError::InconsistentWidth { line: 3, expected: 3, actual: 4 })
+        ));
+        assert!(matches!(
+            power_consumption("101\n1x1\n".as_bytes()),
+            Err(DiagnosticError::InvalidLine(2, BitStringParseError::InvalidBinaryDigit(1, 'x')))
+        ));
+        assert!(matches!(
+            power_consumption("1".repeat(65).as_bytes()),
+            Err(DiagnosticError::WidthTooLarge { line: 1, width: 65 })
+        ));
+
+        let mut counter = ColumnCounter::new();
+
+        assert!(counter.add_line("011").is_ok());
+        assert!(matches!(counter.add_line("01"), Err(DiagnosticError::InconsistentWidth { line: 2, expected: 3, actual: 2 })));
+    }
+}
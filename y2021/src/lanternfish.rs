@@ -1,57 +1,491 @@
-use std::collections::hash_map::HashMap;
-use std::collections::hash_set::HashSet;
-use std::ops::{Add, AddAssign};
+pub const SPAWN_INTERVAL: usize = 7;
+pub const OFFSPRING_DELAY: usize = 2;
 
-const SPAWN_INTERVAL: u64 = 7;
-const OFFSPRING_DELAY: u64 = 2;
+#[derive(PartialEq, Debug)]
+pub enum SpawningError {
+    InvalidInterval(usize),
+    InvalidTimer { index: usize, timer: u64, max: usize },
+    Overflow { days: usize },
+}
+
+// A square matrix over u128, either exact with overflow detection or reduced modulo
+// a u64 so that products always fit.
+#[derive(Clone, Debug, PartialEq)]
+struct Matrix {
+    size: usize,
+    data: Vec<u128>,
+}
+
+impl Matrix {
+    fn identity(size: usize) -> Matrix {
+        let mut data = vec![0; size * size];
+
+        for i in 0..size {
+            data[i * size + i] = 1;
+        }
+
+        Matrix { size, data }
+    }
+
+    fn multiply(&self, other: &Matrix, modulus: Option<u64>) -> Option<Matrix> {
+        let mut data = vec![0_u128; self.size * self.size];
+
+        for i in 0..self.size {
+            for k in 0..self.size {
+                let a = self.data[i * self.size + k];
+
+                if a == 0 {
+                    continue;
+                }
+
+                for j in 0..self.size {
+                    let cell = &mut data[i * self.size + j];
+                    let product = a.checked_mul(other.data[k * self.size + j])?;
+
+                    *cell = match modulus {
+                        Some(m) => (*cell + product % m as u128) % m as u128,
+                        None => cell.checked_add(product)?,
+                    };
+                }
+            }
+        }
+
+        Some(Matrix { size: self.size, data })
+    }
+
+    fn power(&self, mut exponent: usize, modulus: Option<u64>) -> Option<Matrix> {
+        let mut result = Matrix::identity(self.size);
+        let mut base = self.clone();
+
+        if let Some(m) = modulus {
+            result.data.iter_mut().for_each(|v| *v %= m as u128);
+            base.data.iter_mut().for_each(|v| *v %= m as u128);
+        }
 
+        while exponent > 0 {
+            if exponent & 1 == 1 {
+                result = result.multiply(&base, modulus)?;
+            }
+
+            exponent >>= 1;
+
+            if exponent > 0 {
+                base = base.multiply(&base, modulus)?;
+            }
+        }
+
+        Some(result)
+    }
+
+    fn apply(&self, vector: &[u128], modulus: Option<u64>) -> Option<Vec<u128>> {
+        (0..self.size).map(|i| {
+            let mut sum: u128 = 0;
+
+            for (j, v) in vector.iter().enumerate() {
+                // Exact counts can be far above the modulus; reduced, every product stays below m².
+                let v = modulus.map(|m| *v % m as u128).unwrap_or(*v);
+                let product = self.data[i * self.size + j].checked_mul(v)?;
+
+                sum = match modulus {
+                    Some(m) => (sum + product % m as u128) % m as u128,
+                    None => sum.checked_add(product)?,
+                };
+            }
+
+            Some(sum)
+        }).collect()
+    }
+}
+
+// Fish are grouped by their timer. The cohorts live in a ring buffer indexed from
+// `head`, so a day passing is a rotation: the fish at timer 0 move to the last slot
+// as their own offspring, and are added back in at timer interval - 1.
+#[derive(Clone, Debug, PartialEq)]
 pub struct SpawningModel {
-    pub members: HashMap<u64, u64>,
+    pub interval: usize,
+    pub delay: usize,
     pub days: usize,
+    cohorts: Vec<u128>,
+    head: usize,
 }
 
 impl SpawningModel {
-    pub fn new(initial: Vec<u64>) -> SpawningModel {
-        let mut model = SpawningModel { members: HashMap::new(), days: 0 };
+    pub fn new(initial: Vec<u64>) -> Result<SpawningModel, SpawningError> {
+        SpawningModel::with_lifecycle(initial, SPAWN_INTERVAL, OFFSPRING_DELAY)
+    }
 
-        for i in 0..(SPAWN_INTERVAL + OFFSPRING_DELAY) {
-            model.members.insert(i as u64, 0);
+    pub fn with_lifecycle(initial: Vec<u64>, interval: usize, delay: usize) -> Result<SpawningModel, SpawningError> {
+        if interval == 0 {
+            return Err(SpawningError::InvalidInterval(interval));
         }
 
-        initial.into_iter().for_each(|f| {
-            model.members.get_mut(&f).map(|x| x.add_assign(1));
-        });
+        let mut model = SpawningModel { interval, delay, days: 0, cohorts: vec![0; interval + delay], head: 0 };
+        let max = model.cohorts.len() - 1;
+
+        for (index, timer) in initial.into_iter().enumerate() {
+            if timer as usize > max {
+                return Err(SpawningError::InvalidTimer { index, timer, max });
+            }
+
+            model.cohorts[timer as usize] += 1;
+        }
+
+        Ok(model)
+    }
+
+    pub fn count(&self, timer: usize) -> u128 {
+        assert!(timer < self.cohorts.len());
 
-        model
+        self.cohorts[(self.head + timer) % self.cohorts.len()]
     }
 
-    pub fn get_total(&self) -> usize {
-        self.members.values().fold(0_u64, |result, m| result + *m) as usize
+    // Cohort sizes ordered by timer, starting at 0.
+    pub fn counts(&self) -> Vec<u128> {
+        (0..self.cohorts.len()).map(|timer| self.count(timer)).collect()
+    }
+
+    pub fn get_total(&self) -> u128 {
+        self.cohorts.iter().sum()
     }
 
     pub fn print_counts(&self) {
         println!("Printing fish counts");
-        for i in 0..(SPAWN_INTERVAL + OFFSPRING_DELAY) {
-            println!(" - {} DAY(S) REMAINING = {}", i, self.members[&i]);
+        for (i, count) in self.counts().into_iter().enumerate() {
+            println!(" - {} DAY(S) REMAINING = {}", i, count);
+        }
+    }
+
+    // Row t holds the cohorts that end up at timer t after one day.
+    fn transition(&self) -> Matrix {
+        let size = self.cohorts.len();
+        let mut matrix = Matrix { size, data: vec![0; size * size] };
+
+        for t in 0..size - 1 {
+            matrix.data[t * size + t + 1] = 1;
+        }
+
+        matrix.data[(size - 1) * size] = 1;
+        matrix.data[(self.interval - 1) * size] += 1;
+
+        matrix
+    }
+
+    fn project(&self, days: usize, modulus: Option<u64>) -> Option<Vec<u128>> {
+        self.transition().power(days, modulus)?.apply(&self.counts(), modulus)
+    }
+
+    // Jumps ahead in O(log days) matrix products. Exact counts outgrow u128 after a few
+    // thousand days; the model is left untouched when that happens.
+    pub fn advance(&mut self, days: usize) -> Result<(), SpawningError> {
+        self.cohorts = self.project(days, None)
+            .filter(|cohorts| cohorts.iter().try_fold(0_u128, |total, c| total.checked_add(*c)).is_some())
+            .ok_or(SpawningError::Overflow { days })?;
+        self.head = 0;
+        self.days += days;
+
+        Ok(())
+    }
+
+    // Cohort sizes, ordered by timer, `days` days from now reduced modulo `modulus`.
+    pub fn counts_modulo(&self, days: usize, modulus: u64) -> Vec<u64> {
+        assert!(modulus > 0);
+
+        self.project(days, Some(modulus)).unwrap().into_iter().map(|v| v as u64).collect()
+    }
+
+    pub fn total_modulo(&self, days: usize, modulus: u64) -> u64 {
+        (self.counts_modulo(days, modulus).into_iter().map(|v| v as u128).sum::<u128>() % modulus as u128) as u64
+    }
+
+    // The long-run daily growth factor: the real root above 1 of
+    // x^(interval + delay) = x^delay + 1, found by bisection.
+    pub fn growth_rate(&self) -> f64 {
+        let (n, d) = ((self.interval + self.delay) as i32, self.delay as i32);
+        let (mut lo, mut hi) = (1.0_f64, 2.0_f64);
+
+        for _ in 0..100 {
+            let mid = (lo + hi) / 2.0;
+
+            if mid.powi(n) - mid.powi(d) - 1.0 < 0.0 {
+                lo = mid;
+            } else {
+                hi = mid;
+            }
+        }
+
+        (lo + hi) / 2.0
+    }
+
+    // Fails like advance, leaving the model untouched, once the total outgrows u128.
+    pub fn one_day(&mut self) -> Result<(), SpawningError> {
+        let spawning = self.cohorts[self.head];
+        let len = self.cohorts.len();
+
+        // Spawning adds `spawning` fish to the total, so a new total that fits also
+        // covers the cohort they join.
+        if self.cohorts.iter().try_fold(spawning, |total, c| total.checked_add(*c)).is_none() {
+            return Err(SpawningError::Overflow { days: 1 });
+        }
+
+        self.head = (self.head + 1) % len;
+        self.cohorts[(self.head + self.interval - 1) % len] += spawning;
+        self.days += 1;
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DailySample {
+    pub day: usize,
+    pub total: u128,
+    pub counts: Vec<u128>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PopulationRecorder {
+    pub samples: Vec<DailySample>,
+}
+
+impl PopulationRecorder {
+    pub fn new() -> PopulationRecorder {
+        PopulationRecorder::default()
+    }
+
+    pub fn record(&mut self, model: &SpawningModel) {
+        self.samples.push(DailySample { day: model.days, total: model.get_total(), counts: model.counts() });
+    }
+
+    // Records the model after each of the next `days` days. If the population
+    // overflows, the samples up to the last day that fit are kept and the model is
+    // left on that day.
+    pub fn record_days(&mut self, model: &mut SpawningModel, days: usize) -> Result<(), SpawningError> {
+        for _ in 0..days {
+            model.one_day()?;
+            self.record(model);
+        }
+
+        Ok(())
+    }
+
+    // Records the model as it stands and after each of the next `days` days. Use
+    // record_days to keep the samples taken before an overflow.
+    pub fn simulate(model: &mut SpawningModel, days: usize) -> Result<PopulationRecorder, SpawningError> {
+        let mut recorder = PopulationRecorder::new();
+
+        recorder.record(model);
+        recorder.record_days(model, days)?;
+
+        Ok(recorder)
+    }
+
+    // Each sample's total over the previous one; None for the first sample or after
+    // an empty population.
+    pub fn growth_ratios(&self) -> Vec<Option<f64>> {
+        (0..self.samples.len()).map(|i| match i {
+            0 => None,
+            i if self.samples[i - 1].total == 0 => None,
+            i => Some(self.samples[i].total as f64 / self.samples[i - 1].total as f64),
+        }).collect()
+    }
+
+    pub fn to_csv(&self) -> String {
+        let timers = self.samples.first().map(|s| s.counts.len()).unwrap_or(0);
+        let mut output = String::from("day,total");
+
+        for timer in 0..timers {
+            output.push_str(format!(",timer_{}", timer).as_str());
+        }
+
+        output.push_str(",growth\n");
+
+        for (sample, growth) in self.samples.iter().zip(self.growth_ratios()) {
+            output.push_str(format!("{},{}", sample.day, sample.total).as_str());
+
+            for count in sample.counts.iter() {
+                output.push_str(format!(",{}", count).as_str());
+            }
+
+            output.push_str(format!(",{}\n", growth.map(|g| g.to_string()).unwrap_or_default()).as_str());
         }
+
+        output
+    }
+
+    pub fn to_json(&self) -> String {
+        let rows = self.samples.iter().zip(self.growth_ratios()).map(|(sample, growth)| {
+            format!(
+                "{{\"day\":{},\"total\":{},\"counts\":[{}],\"growth\":{}}}",
+                sample.day,
+                sample.total,
+                sample.counts.iter().map(|c| c.to_string()).collect::<Vec<String>>().join(","),
+                growth.map(|g| g.to_string()).unwrap_or_else(|| "null".to_string()),
+            )
+        }).collect::<Vec<String>>();
+
+        format!("[{}]", rows.join(","))
     }
+}
 
-    pub fn one_day(&mut self) {
-        let mut members: HashMap<u64, u64> = HashMap::new();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        (1..(SPAWN_INTERVAL + OFFSPRING_DELAY)).for_each(|i| {
-            members.insert(i - 1, self.members[&i]);
-        });
+    #[test]
+    fn simulates_the_example() {
+        let mut model = SpawningModel::new(vec![3, 4, 3, 1, 2]).unwrap();
 
-        let reproduced_count = self.members[&0];
+        for _ in 0..18 {
+            model.one_day().unwrap();
+        }
 
-        members.insert(
-            SPAWN_INTERVAL - 1,
-            members.get(&(SPAWN_INTERVAL - 1)).map(|x| *x + reproduced_count).unwrap_or(0)
-        );
-        members.insert(SPAWN_INTERVAL + OFFSPRING_DELAY - 1, reproduced_count);
+        assert_eq!(model.get_total(), 26);
+        assert_eq!(model.counts(), vec![3, 5, 3, 2, 2, 1, 5, 1, 4]);
+
+        for _ in 18..256 {
+            model.one_day().unwrap();
+        }
+
+        assert_eq!(model.get_total(), 26984457539);
+    }
+
+    #[test]
+    fn supports_other_lifecycles() {
+        let mut model = SpawningModel::with_lifecycle(vec![0], 1, 0).unwrap();
+
+        for _ in 0..10 {
+            model.one_day().unwrap();
+        }
+
+        assert_eq!(model.get_total(), 1024);
+
+        let mut model = SpawningModel::with_lifecycle(vec![0], 3, 1).unwrap();
+
+        model.one_day().unwrap();
+        assert_eq!(model.counts(), vec![0, 0, 1, 1]);
+    }
+
+    #[test]
+    fn advances_like_the_daily_simulation() {
+        for (interval, delay) in [(7, 2), (1, 0), (3, 4), (5, 1)] {
+            let initial = (0..20).map(|i| i * 7 % (interval + delay) as u64).collect::<Vec<u64>>();
+            let mut daily = SpawningModel::with_lifecycle(initial.clone(), interval, delay).unwrap();
+            let mut jumping = SpawningModel::with_lifecycle(initial, interval, delay).unwrap();
+
+            for days in [0, 1, 5, 17, 40] {
+                for _ in 0..days {
+                    daily.one_day().unwrap();
+                }
+
+                jumping.advance(days).unwrap();
+
+                assert_eq!(jumping.counts(), daily.counts());
+                assert_eq!(jumping.days, daily.days);
+            }
+        }
+
+        let mut model = SpawningModel::new(vec![3, 4, 3, 1, 2]).unwrap();
+
+        model.advance(256).unwrap();
+        assert_eq!(model.get_total(), 26984457539);
+    }
+
+    #[test]
+    fn fast_forwards_modulo() {
+        let model = SpawningModel::new(vec![3, 4, 3, 1, 2]).unwrap();
+        let mut exact = model.clone();
+
+        exact.advance(256).unwrap();
+
+        assert_eq!(model.total_modulo(256, 1_000_000_007), (26984457539_u128 % 1_000_000_007) as u64);
+        assert_eq!(model.counts_modulo(256, 1_000), exact.counts().iter().map(|c| (c % 1_000) as u64).collect::<Vec<u64>>());
+
+        let far = model.counts_modulo(1_000_000_000_000, 998_244_353);
+        let mut stepped = SpawningModel::with_lifecycle(vec![], 7, 2).unwrap();
+        stepped.cohorts = far.iter().map(|c| *c as u128).collect();
+
+        assert_eq!(stepped.counts_modulo(1, 998_244_353), model.counts_modulo(1_000_000_000_001, 998_244_353));
+
+        let mut overflowing = model.clone();
+
+        assert_eq!(overflowing.advance(100_000), Err(SpawningError::Overflow { days: 100_000 }));
+        assert_eq!(overflowing, model);
+    }
+
+    #[test]
+    fn reports_daily_overflow_like_advancing() {
+        let mut model = SpawningModel::with_lifecycle(vec![0], 1, 0).unwrap();
+
+        for _ in 0..127 {
+            model.one_day().unwrap();
+        }
+
+        let full = model.clone();
+
+        assert_eq!(model.get_total(), 1 << 127);
+        assert_eq!(model.one_day(), Err(SpawningError::Overflow { days: 1 }));
+        assert_eq!(model, full);
+        assert_eq!(model.advance(1), Err(SpawningError::Overflow { days: 1 }));
+
+        let mut model = SpawningModel::with_lifecycle(vec![0, 0], 1, 0).unwrap();
+
+        assert_eq!(model.clone().advance(127), Err(SpawningError::Overflow { days: 127 }));
+        assert_eq!(PopulationRecorder::simulate(&mut model.clone(), 200), Err(SpawningError::Overflow { days: 1 }));
+
+        let mut recorder = PopulationRecorder::new();
+
+        assert_eq!(recorder.record_days(&mut model, 200), Err(SpawningError::Overflow { days: 1 }));
+        assert_eq!(model.days, 126);
+        assert_eq!(recorder.samples.len(), 126);
+        assert_eq!(recorder.samples.last().map(|s| (s.day, s.total)), Some((126, 1 << 127)));
+    }
+
+    #[test]
+    fn fast_forwards_large_populations_modulo() {
+        let mut model = SpawningModel::with_lifecycle(vec![0], 1, 0).unwrap();
+
+        for _ in 0..110 {
+            model.one_day().unwrap();
+        }
+
+        assert!(model.get_total() > 1 << 64);
+        assert_eq!(model.total_modulo(0, 1_000_000_007), 804_188_847);
+        assert_eq!(model.total_modulo(40, 1_000_000_007), 769_740_174);
+        // 2^64 is 1 modulo 2^64 - 1, so 2^150 reduces to 2^22.
+        assert_eq!(model.counts_modulo(40, u64::MAX), vec![1 << 22]);
+    }
+
+    #[test]
+    fn records_and_exports_the_population() {
+        let mut model = SpawningModel::new(vec![3, 4, 3, 1, 2]).unwrap();
+        let recorder = PopulationRecorder::simulate(&mut model, 4).unwrap();
+
+        assert_eq!(recorder.samples.iter().map(|s| s.total).collect::<Vec<u128>>(), vec![5, 5, 6, 7, 9]);
+        assert_eq!(recorder.growth_ratios(), vec![None, Some(1.0), Some(1.2), Some(7.0 / 6.0), Some(9.0 / 7.0)]);
+        assert_eq!(recorder.to_csv().lines().take(3).collect::<Vec<&str>>(), vec![
+            "day,total,timer_0,timer_1,timer_2,timer_3,timer_4,timer_5,timer_6,timer_7,timer_8,growth",
+            "0,5,0,1,1,2,1,0,0,0,0,",
+            "1,5,1,1,2,1,0,0,0,0,0,1",
+        ]);
+        assert!(recorder.to_json().starts_with("[{\"day\":0,\"total\":5,\"counts\":[0,1,1,2,1,0,0,0,0],\"growth\":null},{\"day\":1,"));
+        assert_eq!(PopulationRecorder::new().to_json(), "[]");
+    }
+
+    #[test]
+    fn approaches_the_closed_form_growth_rate() {
+        let mut model = SpawningModel::new(vec![3, 4, 3, 1, 2]).unwrap();
+        let recorder = PopulationRecorder::simulate(&mut model, 400).unwrap();
+        let late = recorder.growth_ratios()[300..].iter().map(|g| g.unwrap()).collect::<Vec<f64>>();
+        let average = late.iter().product::<f64>().powf(1.0 / late.len() as f64);
+
+        assert!((model.growth_rate() - 1.0910).abs() < 1e-4);
+        assert!((average - model.growth_rate()).abs() < 1e-3);
+        assert_eq!(SpawningModel::with_lifecycle(vec![0], 1, 0).unwrap().growth_rate(), 2.0);
+    }
 
-        self.members = members;
-        self.days.add_assign(1);
+    #[test]
+    fn rejects_out_of_range_timers() {
+        assert_eq!(SpawningModel::new(vec![3, 9, 1]), Err(SpawningError::InvalidTimer { index: 1, timer: 9, max: 8 }));
+        assert_eq!(SpawningModel::with_lifecycle(vec![], 0, 2), Err(SpawningError::InvalidInterval(0)));
     }
 }
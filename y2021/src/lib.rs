@@ -2,11 +2,17 @@ pub mod submarine;
 pub mod utils;
 pub mod grid;
 pub mod bingo;
+pub mod bingo_server;
 pub mod ocean_vents;
 pub mod lanternfish;
 pub mod display_troubleshooting;
 pub mod either;
 pub mod heightmap;
+pub mod bit_string;
+pub mod packets;
+pub mod diagnostics;
+pub mod random;
+pub mod point;
 
 #[cfg(test)]
 mod tests {
@@ -1,33 +1,12 @@
 use std::collections::hash_map::HashMap;
+use std::collections::{BTreeMap, HashSet};
 use std::num::ParseIntError;
-use std::ops::AddAssign;
 use std::str::FromStr;
+use super::point::{Point2, PointParseError};
 
-#[derive(PartialEq, Debug)]
-pub enum CoordinateParseError {
-    InvalidFormat(ParseIntError),
-    IncorrectValueCount(usize),
-}
+pub type CoordinateParseError = PointParseError<ParseIntError>;
 
-#[derive(Clone, Debug, PartialEq)]
-pub struct Coordinate {
-    pub x: usize,
-    pub y: usize,
-}
-
-impl FromStr for Coordinate {
-    type Err = CoordinateParseError;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        match s.split(",").collect::<Vec<&str>>() {
-            x if x.len() != 2 => Err(CoordinateParseError::IncorrectValueCount(x.len())),
-            x => Ok(Coordinate{
-                x: x[0].parse::<usize>().map_err(|e| CoordinateParseError::InvalidFormat(e))?,
-                y: x[1].parse::<usize>().map_err(|e| CoordinateParseError::InvalidFormat(e))?,
-            }),
-        }
-    }
-}
+pub type Coordinate = Point2<usize>;
 
 #[derive(PartialEq, Debug)]
 pub enum RayParseError {
@@ -51,38 +30,104 @@ impl Ray {
         self.src.x == self.dst.x
     }
 
-    pub fn points(&self) -> Vec<Coordinate> {
-        let mut result: Vec<Coordinate> = Vec::new();
-        let mut position = self.src.clone();
+    pub fn slope(&self) -> Result<Slope, RaySlopeError> {
+        let (dx, dy) = self.delta();
 
-        loop {
-            result.push(position.clone());
+        match (dx, dy) {
+            (_, 0) => Ok(Slope::Horizontal),
+            (0, _) => Ok(Slope::Vertical),
+            (dx, dy) if dx == dy => Ok(Slope::Diagonal),
+            (dx, dy) if dx == -dy => Ok(Slope::AntiDiagonal),
+            (dx, dy) => Err(RaySlopeError::Unsupported { dx, dy }),
+        }
+    }
 
-            if position == self.dst {
-                break;
-            }
+    pub fn points(&self) -> Result<RayPoints, RaySlopeError> {
+        self.slope().map(|_| self.bresenham_points())
+    }
 
-            if position.y != self.dst.y {
-                if position.y < self.dst.y {
-                    position.y += 1;
-                } else {
-                    position.y -= 1;
-                }
-            }
+    // Walks any line, approximating angles other than 0, 45 and 90 degrees. On the
+    // supported slopes this visits exactly the same cells as points().
+    pub fn bresenham_points(&self) -> RayPoints {
+        let (dx, dy) = self.delta();
 
-            if position.x != self.dst.x {
-                if position.x < self.dst.x {
-                    position.x += 1;
-                } else {
-                    position.x -= 1;
-                }
-            }
+        RayPoints {
+            x: self.src.x as i64,
+            y: self.src.y as i64,
+            dx: dx.abs(),
+            dy: -dy.abs(),
+            step_x: dx.signum(),
+            step_y: dy.signum(),
+            error: dx.abs() - dy.abs(),
+            remaining: dx.abs().max(dy.abs()) as usize + 1,
+        }
+    }
+
+    fn delta(&self) -> (i64, i64) {
+        (self.dst.x as i64 - self.src.x as i64, self.dst.y as i64 - self.src.y as i64)
+    }
+}
+
+// A single point counts as horizontal. Diagonal rays keep x - y constant and
+// anti-diagonal rays keep x + y constant.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Slope {
+    Horizontal,
+    Vertical,
+    Diagonal,
+    AntiDiagonal,
+}
+
+#[derive(PartialEq, Debug)]
+pub enum RaySlopeError {
+    Unsupported { dx: i64, dy: i64 },
+}
+
+#[derive(Clone, Debug)]
+pub struct RayPoints {
+    x: i64,
+    y: i64,
+    dx: i64,
+    dy: i64,
+    step_x: i64,
+    step_y: i64,
+    error: i64,
+    remaining: usize,
+}
+
+impl Iterator for RayPoints {
+    type Item = Coordinate;
+
+    fn next(&mut self) -> Option<Coordinate> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let result = Coordinate { x: self.x as usize, y: self.y as usize };
+        let doubled = 2 * self.error;
+
+        if doubled >= self.dy {
+            self.error += self.dy;
+            self.x += self.step_x;
+        }
+
+        if doubled <= self.dx {
+            self.error += self.dx;
+            self.y += self.step_y;
         }
 
-        return result;
+        self.remaining -= 1;
+
+        Some(result)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
     }
 }
 
+impl ExactSizeIterator for RayPoints {}
+
 impl FromStr for Ray {
     type Err = RayParseError;
 
@@ -97,10 +142,22 @@ impl FromStr for Ray {
     }
 }
 
+// The result of adding or removing a ray: the number of cells covered by two or
+// more vents afterwards, and how much that changed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VentUpdate {
+    pub id: usize,
+    pub overlaps: u64,
+    pub delta: i64,
+}
+
 pub struct OceanVentMap {
     pub rows: usize,
     pub cols: usize,
-    pub vents: HashMap<(usize, usize), u32>,
+    pub vents: HashMap<Coordinate, u32>,
+    rays: Vec<Option<Ray>>,
+    covering: HashMap<Coordinate, Vec<usize>>,
+    overlaps: u64,
 }
 
 impl OceanVentMap {
@@ -109,6 +166,9 @@ impl OceanVentMap {
             rows: 0,
             cols: 0,
             vents: HashMap::new(),
+            rays: Vec::new(),
+            covering: HashMap::new(),
+            overlaps: 0,
         }
     }
 
@@ -121,30 +181,1127 @@ impl OceanVentMap {
             self.rows = y + 1;
         }
 
-        match self.vents.get_mut(&(x, y)) {
-            Some(n) => {
-                n.add_assign(1);
-            },
-            None => {
-                self.vents.insert((x, y), 1);
+        let count = self.vents.entry(Coordinate { x, y }).or_insert(0);
+        *count += 1;
+
+        if *count == 2 {
+            self.overlaps += 1;
+        }
+    }
+
+    // Rows and columns only ever grow, so removing vents leaves the drawing size alone.
+    fn decrement(&mut self, coordinate: Coordinate) {
+        let count = self.vents.get_mut(&coordinate).expect("decremented a cell without vents");
+        *count -= 1;
+
+        match *count {
+            0 => {
+                self.vents.remove(&coordinate);
             },
+            1 => self.overlaps -= 1,
+            _ => (),
         }
     }
 
+    // Cells covered by two or more vents, kept up to date as the map changes.
+    pub fn overlaps(&self) -> u64 {
+        self.overlaps
+    }
+
+    pub fn add_ray(&mut self, ray: Ray) -> Result<VentUpdate, RaySlopeError> {
+        let before = self.overlaps;
+        let id = self.rays.len();
+
+        for coordinate in ray.points()? {
+            self.increment(coordinate.x, coordinate.y);
+            self.covering.entry(coordinate).or_default().push(id);
+        }
+
+        self.rays.push(Some(ray));
+
+        Ok(VentUpdate { id, overlaps: self.overlaps, delta: self.overlaps as i64 - before as i64 })
+    }
+
+    pub fn remove_ray(&mut self, id: usize) -> Option<VentUpdate> {
+        let ray = self.rays.get_mut(id)?.take()?;
+        let before = self.overlaps;
+
+        for coordinate in ray.bresenham_points() {
+            self.decrement(coordinate);
+
+            if let Some(ids) = self.covering.get_mut(&coordinate) {
+                ids.retain(|i| *i != id);
+
+                if ids.is_empty() {
+                    self.covering.remove(&coordinate);
+                }
+            }
+        }
+
+        Some(VentUpdate { id, overlaps: self.overlaps, delta: self.overlaps as i64 - before as i64 })
+    }
+
+    pub fn ray(&self, id: usize) -> Option<&Ray> {
+        self.rays.get(id).and_then(|r| r.as_ref())
+    }
+
+    // Ids of the rays added with add_ray that pass through a cell, oldest first.
+    pub fn rays_covering(&self, coordinate: &Coordinate) -> &[usize] {
+        self.covering.get(coordinate).map(|ids| ids.as_slice()).unwrap_or(&[])
+    }
+
     pub fn draw(&self) -> String {
+        if self.vents.is_empty() {
+            return String::new();
+        }
+
+        let viewport = Region::new(Coordinate { x: 0, y: 0 }, Coordinate { x: self.cols - 1, y: self.rows - 1 });
+
+        HeatmapRenderer::new().with_viewport(viewport).render(self)
+    }
+
+    // The smallest region holding every vent.
+    pub fn bounds(&self) -> Option<Region> {
+        let min = Coordinate { x: self.vents.keys().map(|c| c.x).min()?, y: self.vents.keys().map(|c| c.y).min()? };
+        let max = Coordinate { x: self.vents.keys().map(|c| c.x).max()?, y: self.vents.keys().map(|c| c.y).max()? };
+
+        Some(Region::new(min, max))
+    }
+
+    pub fn index(&self, threshold: u32) -> VentIndex {
+        VentIndex::new(self, threshold)
+    }
+}
+
+// The cells with at least `threshold` vents, indexed so that any rectangle can be
+// counted in O(log² n): a Fenwick tree over their distinct columns whose nodes keep
+// the rows of the cells they cover in order. It takes O(n log n) space for n such
+// cells, however far apart they are.
+#[derive(Clone, Debug)]
+pub struct VentIndex {
+    pub threshold: u32,
+    bounds: Option<Region>,
+    cells: Vec<Coordinate>,
+    xs: Vec<usize>,
+    columns: Vec<Vec<usize>>,
+}
+
+impl VentIndex {
+    pub fn new(map: &OceanVentMap, threshold: u32) -> VentIndex {
+        let mut cells = map.vents.iter().filter(|(_, count)| **count >= threshold).map(|(c, _)| *c).collect::<Vec<Coordinate>>();
+        let mut xs = cells.iter().map(|c| c.x).collect::<Vec<usize>>();
+
+        cells.sort_unstable_by_key(|c| (c.y, c.x));
+        xs.sort_unstable();
+        xs.dedup();
+
+        let mut columns: Vec<Vec<usize>> = vec![Vec::new(); xs.len()];
+
+        for c in cells.iter() {
+            let mut i = xs.partition_point(|x| *x < c.x) + 1;
+
+            while i <= xs.len() {
+                columns[i - 1].push(c.y);
+                i += i & i.wrapping_neg();
+            }
+        }
+
+        // Cells are added in row order, so every node is already sorted.
+        VentIndex { threshold, bounds: map.bounds(), cells, xs, columns }
+    }
+
+    // Cells in the first `k` distinct columns with a row in [y1, y2].
+    fn prefix(&self, k: usize, y1: usize, y2: usize) -> u64 {
+        let mut i = k;
+        let mut count = 0;
+
+        while i > 0 {
+            let (lo, hi) = VentIndex::range(&self.columns[i - 1], y1, y2);
+            count += (hi - lo) as u64;
+            i -= i & i.wrapping_neg();
+        }
+
+        count
+    }
+
+    // The half-open range of sorted values falling inside [lo, hi].
+    fn range(values: &[usize], lo: usize, hi: usize) -> (usize, usize) {
+        let start = values.partition_point(|v| *v < lo);
+
+        (start, values.partition_point(|v| *v <= hi).max(start))
+    }
+
+    pub fn count_in(&self, region: &Region) -> u64 {
+        let (x1, x2) = VentIndex::range(&self.xs, region.min.x, region.max.x);
+
+        self.prefix(x2, region.min.y, region.max.y) - self.prefix(x1, region.min.y, region.max.y)
+    }
+
+    // Window starts in [first, last] where the cells inside can change, in order.
+    fn window_starts(values: &[usize], first: usize, last: usize, span: usize) -> Vec<usize> {
+        let mut starts = values.iter()
+            .flat_map(|v| [(v + 1).saturating_sub(span), v + 1])
+            .filter(|start| (first..=last).contains(start))
+            .chain([first])
+            .collect::<Vec<usize>>();
+
+        starts.sort_unstable();
+        starts.dedup();
+        starts
+    }
+
+    // The width × height window holding the most qualifying cells, earliest in
+    // reading order on ties. Windows larger than the map are anchored at its corner.
+    pub fn densest_window(&self, width: usize, height: usize) -> Option<(Region, u64)> {
+        let b = self.bounds.as_ref()?;
+
+        if width == 0 || height == 0 {
+            return None;
+        }
+
+        let (span_x, span_y) = (width.min(b.max.x - b.min.x + 1), height.min(b.max.y - b.min.y + 1));
+        let mut ys = self.cells.iter().map(|c| c.y).collect::<Vec<usize>>();
+        ys.dedup();
+
+        // The count only changes at these starts, so the first of each run of equal
+        // windows is always among them.
+        let columns = VentIndex::window_starts(&self.xs, b.min.x, b.max.x + 1 - span_x, span_x);
+        let rows = VentIndex::window_starts(&ys, b.min.y, b.max.y + 1 - span_y, span_y);
+
+        // Sweeping the rows, each cell inside the band adds one to every column start
+        // whose window would hold it.
+        let mut counts = WindowCounts::new(columns.len());
+        let update = |counts: &mut WindowCounts, c: &Coordinate, change: i64| {
+            let (lo, hi) = VentIndex::range(&columns, (c.x + 1).saturating_sub(span_x), c.x);
+
+            if lo < hi {
+                counts.add(lo, hi - 1, change);
+            }
+        };
+        let (mut entered, mut left) = (0, 0);
+        let mut best: Option<(usize, usize, u64)> = None;
+
+        for y in rows {
+            while entered < self.cells.len() && self.cells[entered].y < y + span_y {
+                update(&mut counts, &self.cells[entered], 1);
+                entered += 1;
+            }
+
+            while left < self.cells.len() && self.cells[left].y < y {
+                update(&mut counts, &self.cells[left], -1);
+                left += 1;
+            }
+
+            let (column, count) = counts.best();
+
+            if best.map(|(_, _, c)| count as u64 > c).unwrap_or(true) {
+                best = Some((columns[column], y, count as u64));
+            }
+        }
+
+        best.map(|(x, y, count)| {
+            let min = Coordinate { x, y };
+            let max = Coordinate { x: x + width - 1, y: y + height - 1 };
+
+            (Region { min, max }, count)
+        })
+    }
+}
+
+// A segment tree over window starts supporting range additions and finding the
+// leftmost start with the highest count. Each node holds the best count below it
+// plus whatever was added to its whole range.
+struct WindowCounts {
+    len: usize,
+    best: Vec<i64>,
+    added: Vec<i64>,
+}
+
+impl WindowCounts {
+    fn new(len: usize) -> WindowCounts {
+        WindowCounts { len, best: vec![0; 4 * len.max(1)], added: vec![0; 4 * len.max(1)] }
+    }
+
+    fn add(&mut self, lo: usize, hi: usize, change: i64) {
+        self.add_below(1, 0, self.len - 1, lo, hi, change);
+    }
+
+    fn add_below(&mut self, node: usize, node_lo: usize, node_hi: usize, lo: usize, hi: usize, change: i64) {
+        if hi < node_lo || node_hi < lo {
+            return;
+        }
+
+        if lo <= node_lo && node_hi <= hi {
+            self.best[node] += change;
+            self.added[node] += change;
+
+            return;
+        }
+
+        let mid = (node_lo + node_hi) / 2;
+
+        self.add_below(2 * node, node_lo, mid, lo, hi, change);
+        self.add_below(2 * node + 1, mid + 1, node_hi, lo, hi, change);
+        self.best[node] = self.best[2 * node].max(self.best[2 * node + 1]) + self.added[node];
+    }
+
+    fn best(&self) -> (usize, i64) {
+        let (mut node, mut lo, mut hi) = (1, 0, self.len - 1);
+
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+
+            if self.best[2 * node] >= self.best[2 * node + 1] {
+                (node, hi) = (2 * node, mid);
+            } else {
+                (node, lo) = (2 * node + 1, mid + 1);
+            }
+        }
+
+        (lo, self.best[1])
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Palette {
+    // The puzzle's own notation: '.' for empty cells, the count itself up to 9 and '#'
+    // beyond, never rescaled.
+    Counts,
+    // The first character marks empty cells; counts are spread over the rest.
+    Ramp(Vec<char>),
+    // 24-bit ANSI colours running from blue for a single vent to red for the busiest cell.
+    Gradient,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct HeatmapRenderer {
+    pub viewport: Option<Region>,
+    pub max_size: Option<(usize, usize)>,
+    pub palette: Palette,
+}
+
+impl Default for HeatmapRenderer {
+    fn default() -> Self {
+        HeatmapRenderer { viewport: None, max_size: None, palette: Palette::Counts }
+    }
+}
+
+impl HeatmapRenderer {
+    // Used when no max_size is given, so a map spanning billions of cells still renders.
+    pub const DEFAULT_MAX_SIZE: (usize, usize) = (1000, 1000);
+
+    pub fn new() -> HeatmapRenderer {
+        HeatmapRenderer::default()
+    }
+
+    pub fn gradient() -> HeatmapRenderer {
+        HeatmapRenderer { palette: Palette::Gradient, ..HeatmapRenderer::default() }
+    }
+
+    pub fn with_ramp(mut self, ramp: &str) -> HeatmapRenderer {
+        assert!(ramp.chars().count() >= 2, "a ramp needs an empty and at least one occupied character");
+
+        self.palette = Palette::Ramp(ramp.chars().collect());
+        self
+    }
+
+    // Without a viewport the map is cropped to the cells holding vents.
+    pub fn with_viewport(mut self, viewport: Region) -> HeatmapRenderer {
+        self.viewport = Some(viewport);
+        self
+    }
+
+    // Maps larger than this are downsampled, each character standing for the busiest
+    // cell of the block it covers.
+    pub fn with_max_size(mut self, width: usize, height: usize) -> HeatmapRenderer {
+        self.max_size = Some((width.max(1), height.max(1)));
+        self
+    }
+
+    fn level(count: u32, max: u32, levels: u32) -> u32 {
+        if count == 0 || max <= levels {
+            return count.min(levels);
+        }
+
+        ((count as u64 * levels as u64).div_ceil(max as u64) as u32).max(1)
+    }
+
+    fn cell(&self, count: u32, max: u32) -> String {
+        match &self.palette {
+            Palette::Counts => match count {
+                0 => ".".to_string(),
+                1..=9 => count.to_string(),
+                _ => "#".to_string(),
+            },
+            Palette::Ramp(ramp) => ramp[HeatmapRenderer::level(count, max, ramp.len() as u32 - 1) as usize].to_string(),
+            Palette::Gradient if count == 0 => " ".to_string(),
+            Palette::Gradient => {
+                let heat = (count as u64 * 255 / max as u64) as u32;
+
+                format!("\x1b[38;2;{};{};{}m\u{2588}\x1b[0m", heat, 64, 255 - heat)
+            },
+        }
+    }
+
+    pub fn render(&self, map: &OceanVentMap) -> String {
+        let viewport = match self.viewport.clone().or_else(|| map.bounds()) {
+            Some(viewport) => viewport,
+            None => return String::new(),
+        };
+
+        let (width, height) = (viewport.max.x - viewport.min.x + 1, viewport.max.y - viewport.min.y + 1);
+        let (max_width, max_height) = self.max_size.unwrap_or(HeatmapRenderer::DEFAULT_MAX_SIZE);
+        let (block_x, block_y) = (width.div_ceil(max_width), height.div_ceil(max_height));
+        let (cols, rows) = (width.div_ceil(block_x), height.div_ceil(block_y));
+        let mut blocks = vec![0_u32; cols * rows];
+
+        for (c, count) in map.vents.iter() {
+            if viewport.contains(c) {
+                let block = (c.y - viewport.min.y) / block_y * cols + (c.x - viewport.min.x) / block_x;
+                blocks[block] = blocks[block].max(*count);
+            }
+        }
+
+        let max = blocks.iter().copied().max().unwrap_or(0);
         let mut output = String::new();
 
-        for y in 0..self.rows {
-            for x in 0..self.cols {
-                output.extend(match self.vents.get(&(x, y)) {
-                    Some(v) => v.to_string(),
-                    None => ".".to_string()
-                }.chars());
+        for row in blocks.chunks(cols) {
+            for count in row {
+                output.push_str(self.cell(*count, max).as_str());
             }
 
             output.push('\n');
         }
 
-        return output;
+        output
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub enum VentSweepError {
+    UnsupportedRay(usize),
+}
+
+// A ray as a closed interval [lo, hi] along a line. Horizontal, diagonal and
+// anti-diagonal lines are parameterised by x and vertical lines by y, with the
+// constant being y, x, x - y and x + y respectively.
+#[derive(Clone, Copy, Debug)]
+struct Span {
+    orientation: Slope,
+    constant: i64,
+    lo: i64,
+    hi: i64,
+}
+
+impl Span {
+    fn from_ray(ray: &Ray) -> Result<Span, RaySlopeError> {
+        let (x1, y1, x2, y2) = (ray.src.x as i64, ray.src.y as i64, ray.dst.x as i64, ray.dst.y as i64);
+        let orientation = ray.slope()?;
+        let (a, b) = (Span::position(orientation, x1, y1), Span::position(orientation, x2, y2));
+
+        Ok(Span { orientation, constant: Span::constant(orientation, x1, y1), lo: a.min(b), hi: a.max(b) })
+    }
+
+    // The value shared by every cell on a line of this orientation.
+    fn constant(orientation: Slope, x: i64, y: i64) -> i64 {
+        match orientation {
+            Slope::Horizontal => y,
+            Slope::Vertical => x,
+            Slope::Diagonal => x - y,
+            Slope::AntiDiagonal => x + y,
+        }
+    }
+
+    fn position(orientation: Slope, x: i64, y: i64) -> i64 {
+        match orientation {
+            Slope::Vertical => y,
+            _ => x,
+        }
+    }
+
+    fn point(orientation: Slope, constant: i64, t: i64) -> (i64, i64) {
+        match orientation {
+            Slope::Horizontal => (t, constant),
+            Slope::Vertical => (constant, t),
+            Slope::Diagonal => (t, t - constant),
+            Slope::AntiDiagonal => (t, constant - t),
+        }
+    }
+
+    // The part of the span inside a region, if any.
+    fn clip(&self, region: &Region) -> Option<Span> {
+        let (x1, y1, x2, y2) = (region.min.x as i64, region.min.y as i64, region.max.x as i64, region.max.y as i64);
+        let c = self.constant;
+        let (lo, hi) = match self.orientation {
+            Slope::Horizontal if (y1..=y2).contains(&c) => (x1, x2),
+            Slope::Vertical if (x1..=x2).contains(&c) => (y1, y2),
+            Slope::Diagonal => (x1.max(y1 + c), x2.min(y2 + c)),
+            Slope::AntiDiagonal => (x1.max(c - y2), x2.min(c - y1)),
+            _ => return None,
+        };
+        let (lo, hi) = (lo.max(self.lo), hi.min(self.hi));
+
+        (lo <= hi).then_some(Span { lo, hi, ..*self })
+    }
+
+    // The cell where two lines of different orientations meet, if they meet on one.
+    fn crossing((p, a): (Slope, i64), (q, b): (Slope, i64)) -> Option<(i64, i64)> {
+        use Slope::*;
+
+        match (p, q) {
+            (p, q) if p == q => None,
+            (Vertical, _) => Some(Span::point(q, b, a)),
+            (_, Vertical) => Some(Span::point(p, a, b)),
+            (Horizontal, Diagonal) | (Diagonal, Horizontal) => {
+                let (y, difference) = if p == Horizontal { (a, b) } else { (b, a) };
+                Some((difference + y, y))
+            },
+            (Horizontal, _) | (_, Horizontal) => {
+                let (y, sum) = if p == Horizontal { (a, b) } else { (b, a) };
+                Some((sum - y, y))
+            },
+            _ => {
+                let (difference, sum) = if p == Diagonal { (a, b) } else { (b, a) };
+
+                if (difference + sum) % 2 != 0 {
+                    return None;
+                }
+
+                Some(((difference + sum) / 2, (sum - difference) / 2))
+            },
+        }
+    }
+}
+
+// Per line: the intervals covered at least once and those covered at least twice.
+struct LineCoverage {
+    covered: Vec<(i64, i64)>,
+    overlapping: Vec<(i64, i64)>,
+}
+
+impl LineCoverage {
+    fn new(spans: &[Span]) -> LineCoverage {
+        let mut events: Vec<(i64, i32)> = spans.iter().flat_map(|s| [(s.lo, 1), (s.hi + 1, -1)]).collect();
+        let mut coverage = LineCoverage { covered: Vec::new(), overlapping: Vec::new() };
+        let mut depth = 0;
+        let mut starts: (i64, i64) = (0, 0);
+
+        events.sort();
+
+        for (position, change) in events {
+            let previous = depth;
+            depth += change;
+
+            match (previous, depth) {
+                (0, 1) => starts.0 = position,
+                (1, 0) => LineCoverage::extend(&mut coverage.covered, starts.0, position - 1),
+                (1, 2) => starts.1 = position,
+                (2, 1) => LineCoverage::extend(&mut coverage.overlapping, starts.1, position - 1),
+                _ => (),
+            }
+        }
+
+        coverage
+    }
+
+    fn extend(intervals: &mut Vec<(i64, i64)>, lo: i64, hi: i64) {
+        match intervals.last_mut() {
+            Some(last) if last.1 + 1 >= lo => last.1 = last.1.max(hi),
+            _ => intervals.push((lo, hi)),
+        }
+    }
+
+    fn covered_at(&self, t: i64) -> bool {
+        LineCoverage::within(&self.covered, t)
+    }
+
+    fn overlaps_at(&self, t: i64) -> bool {
+        LineCoverage::within(&self.overlapping, t)
+    }
+
+    fn within(intervals: &[(i64, i64)], t: i64) -> bool {
+        let i = intervals.partition_point(|(_, hi)| *hi < t);
+
+        intervals.get(i).map(|(lo, _)| *lo <= t).unwrap_or(false)
+    }
+}
+
+// Counts the cells covered by two or more rays without visiting every cell. Rays on
+// the same line are merged with a one dimensional sweep; rays on different lines can
+// only share a single cell, so each merged span is only tested against the lines of
+// another orientation whose constant it passes through.
+pub fn count_overlaps(rays: &[Ray]) -> Result<u64, VentSweepError> {
+    const ORIENTATIONS: [Slope; 4] = [Slope::Horizontal, Slope::Vertical, Slope::Diagonal, Slope::AntiDiagonal];
+    let mut lines: HashMap<(Slope, i64), Vec<Span>> = HashMap::new();
+
+    for (i, ray) in rays.iter().enumerate() {
+        let span = Span::from_ray(ray).map_err(|_| VentSweepError::UnsupportedRay(i))?;
+        lines.entry((span.orientation, span.constant)).or_default().push(span);
+    }
+
+    let coverage: HashMap<(Slope, i64), LineCoverage> = lines.iter()
+        .map(|(key, spans)| (*key, LineCoverage::new(spans)))
+        .collect();
+
+    let mut by_orientation: HashMap<Slope, BTreeMap<i64, &LineCoverage>> = HashMap::new();
+
+    for ((orientation, constant), c) in coverage.iter() {
+        by_orientation.entry(*orientation).or_default().insert(*constant, c);
+    }
+
+    let mut crossings: HashSet<(i64, i64)> = HashSet::new();
+
+    for (i, p) in ORIENTATIONS.iter().enumerate() {
+        for q in ORIENTATIONS[i + 1..].iter() {
+            let (ps, qs) = match (by_orientation.get(p), by_orientation.get(q)) {
+                (Some(ps), Some(qs)) => (ps, qs),
+                _ => continue,
+            };
+
+            for (a, line) in ps.iter() {
+                for (lo, hi) in line.covered.iter() {
+                    let ((x1, y1), (x2, y2)) = (Span::point(*p, *a, *lo), Span::point(*p, *a, *hi));
+                    let (b1, b2) = (Span::constant(*q, x1, y1), Span::constant(*q, x2, y2));
+
+                    for (b, other) in qs.range(b1.min(b2)..=b1.max(b2)) {
+                        let crossing = Span::crossing((*p, *a), (*q, *b)).filter(|(x, y)| {
+                            (*lo..=*hi).contains(&Span::position(*p, *x, *y)) && other.covered_at(Span::position(*q, *x, *y))
+                        });
+
+                        if let Some(point) = crossing {
+                            crossings.insert(point);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let mut total: i64 = coverage.values()
+        .flat_map(|c| c.overlapping.iter())
+        .map(|(lo, hi)| hi - lo + 1)
+        .sum();
+
+    // A crossing already inside one line's overlap was counted above; inside several,
+    // it was counted once per line. Only one line of each orientation passes through it.
+    for (x, y) in crossings {
+        let counted = ORIENTATIONS.iter().filter(|orientation| {
+            coverage.get(&(**orientation, Span::constant(**orientation, x, y)))
+                .map(|c| c.overlaps_at(Span::position(**orientation, x, y)))
+                .unwrap_or(false)
+        }).count() as i64;
+
+        total += if counted == 0 { 1 } else { 1 - counted };
+    }
+
+    Ok(total as u64)
+}
+
+// An inclusive rectangle of cells.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Region {
+    pub min: Coordinate,
+    pub max: Coordinate,
+}
+
+impl Region {
+    pub fn new(min: Coordinate, max: Coordinate) -> Region {
+        Region {
+            min: Coordinate { x: min.x.min(max.x), y: min.y.min(max.y) },
+            max: Coordinate { x: min.x.max(max.x), y: min.y.max(max.y) },
+        }
+    }
+
+    pub fn contains(&self, coordinate: &Coordinate) -> bool {
+        (self.min.x..=self.max.x).contains(&coordinate.x) && (self.min.y..=self.max.y).contains(&coordinate.y)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct VentScanner {
+    pub slopes: Vec<Slope>,
+    pub threshold: u32,
+    pub region: Option<Region>,
+}
+
+impl Default for VentScanner {
+    fn default() -> Self {
+        VentScanner {
+            slopes: vec![Slope::Horizontal, Slope::Vertical, Slope::Diagonal, Slope::AntiDiagonal],
+            threshold: 2,
+            region: None,
+        }
+    }
+}
+
+impl VentScanner {
+    pub fn new() -> VentScanner {
+        VentScanner::default()
+    }
+
+    pub fn axis_aligned() -> VentScanner {
+        VentScanner::new().with_slopes(&[Slope::Horizontal, Slope::Vertical])
+    }
+
+    pub fn with_slopes(mut self, slopes: &[Slope]) -> VentScanner {
+        self.slopes = slopes.to_vec();
+        self
+    }
+
+    // Cells need at least this many vents to be reported.
+    pub fn with_threshold(mut self, threshold: u32) -> VentScanner {
+        self.threshold = threshold;
+        self
+    }
+
+    pub fn with_region(mut self, region: Region) -> VentScanner {
+        self.region = Some(region);
+        self
+    }
+
+    fn selected<'a>(&self, rays: &'a [Ray]) -> Result<Vec<&'a Ray>, VentSweepError> {
+        let mut result = Vec::new();
+
+        for (i, ray) in rays.iter().enumerate() {
+            let slope = ray.slope().map_err(|_| VentSweepError::UnsupportedRay(i))?;
+
+            if self.slopes.contains(&slope) {
+                result.push(ray);
+            }
+        }
+
+        Ok(result)
+    }
+
+    // Overlapping cells and their vent counts, ordered by row and then column.
+    pub fn scan(&self, rays: &[Ray]) -> Result<Vec<(Coordinate, u32)>, VentSweepError> {
+        let mut map = OceanVentMap::new();
+
+        // Every selected ray has a supported slope, so each one makes a span.
+        for span in self.selected(rays)?.into_iter().filter_map(|ray| Span::from_ray(ray).ok()) {
+            let clipped = match &self.region {
+                Some(region) => span.clip(region),
+                None => Some(span),
+            };
+
+            for t in clipped.into_iter().flat_map(|span| span.lo..=span.hi) {
+                let (x, y) = Span::point(span.orientation, span.constant, t);
+                map.increment(x as usize, y as usize);
+            }
+        }
+
+        let mut result: Vec<(Coordinate, u32)> = map.vents.into_iter()
+            .filter(|(_, count)| *count >= self.threshold)
+            .collect();
+
+        result.sort_by_key(|(c, _)| (c.y, c.x));
+
+        Ok(result)
+    }
+
+    // Uses the sweep for the usual "two or more anywhere" question and falls back to
+    // scanning the cells otherwise.
+    pub fn count(&self, rays: &[Ray]) -> Result<u64, VentSweepError> {
+        if self.threshold == 2 && self.region.is_none() {
+            let selected = self.selected(rays)?.into_iter().cloned().collect::<Vec<Ray>>();
+
+            return count_overlaps(&selected);
+        }
+
+        self.scan(rays).map(|cells| cells.len() as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::Rng;
+
+    const EXAMPLE: &str = "0,9 -> 5,9\n8,0 -> 0,8\n9,4 -> 3,4\n2,2 -> 2,1\n7,0 -> 7,4\n6,4 -> 2,0\n0,9 -> 2,9\n3,4 -> 1,4\n0,0 -> 8,8\n5,5 -> 8,2";
+
+    fn rasterized_overlaps(rays: &[Ray]) -> u64 {
+        let mut map = OceanVentMap::new();
+
+        for ray in rays {
+            for coordinate in ray.points().unwrap() {
+                map.increment(coordinate.x, coordinate.y);
+            }
+        }
+
+        map.vents.values().filter(|v| **v > 1).count() as u64
+    }
+
+    fn random_ray(rng: &mut Rng, size: u64) -> Ray {
+        let src = Coordinate { x: rng.below(size) as usize, y: rng.below(size) as usize };
+        let length = rng.below(size) as i64;
+        let (dx, dy) = [(1, 0), (0, 1), (1, 1), (1, -1), (-1, 0), (0, -1), (-1, -1), (-1, 1)][rng.below(8) as usize];
+        let clamp = |v: i64| v.clamp(0, size as i64 - 1) as usize;
+        let steps = (0..=length).take_while(|i| {
+            (0..size as i64).contains(&(src.x as i64 + dx * i)) && (0..size as i64).contains(&(src.y as i64 + dy * i))
+        }).count() as i64 - 1;
+
+        Ray { dst: Coordinate { x: clamp(src.x as i64 + dx * steps), y: clamp(src.y as i64 + dy * steps) }, src }
+    }
+
+    fn xy(points: RayPoints) -> Vec<(usize, usize)> {
+        points.map(|c| (c.x, c.y)).collect()
+    }
+
+    #[test]
+    fn classifies_slopes() {
+        let slope = |s: &str| Ray::from_str(s).unwrap().slope();
+
+        assert_eq!(slope("3,4 -> 1,4"), Ok(Slope::Horizontal));
+        assert_eq!(slope("7,0 -> 7,4"), Ok(Slope::Vertical));
+        assert_eq!(slope("8,8 -> 0,0"), Ok(Slope::Diagonal));
+        assert_eq!(slope("8,0 -> 0,8"), Ok(Slope::AntiDiagonal));
+        assert_eq!(slope("2,2 -> 2,2"), Ok(Slope::Horizontal));
+        assert_eq!(slope("0,0 -> 4,2"), Err(RaySlopeError::Unsupported { dx: 4, dy: 2 }));
+    }
+
+    #[test]
+    fn walks_rays_lazily() {
+        let ray = Ray::from_str("5,5 -> 8,2").unwrap();
+        let points = ray.points().unwrap();
+
+        assert_eq!(points.len(), 4);
+        assert_eq!(xy(points), vec![(5, 5), (6, 4), (7, 3), (8, 2)]);
+        assert_eq!(xy(Ray::from_str("9,4 -> 7,4").unwrap().points().unwrap()), vec![(9, 4), (8, 4), (7, 4)]);
+        assert_eq!(Ray::from_str("0,0 -> 3000000000,0").unwrap().points().unwrap().len(), 3_000_000_001);
+        assert!(Ray::from_str("0,0 -> 4,2").unwrap().points().is_err());
+    }
+
+    #[test]
+    fn walks_other_angles_with_bresenham() {
+        let ray = Ray::from_str("0,0 -> 4,2").unwrap();
+
+        assert_eq!(xy(ray.bresenham_points()), vec![(0, 0), (1, 1), (2, 1), (3, 2), (4, 2)]);
+        assert_eq!(xy(Ray::from_str("1,6 -> 0,0").unwrap().bresenham_points()).len(), 7);
+        assert_eq!(xy(Ray::from_str("6,4 -> 2,0").unwrap().bresenham_points()), xy(Ray::from_str("6,4 -> 2,0").unwrap().points().unwrap()));
+    }
+
+    #[test]
+    fn counts_the_example_overlaps() {
+        let rays = EXAMPLE.lines().map(|l| Ray::from_str(l).unwrap()).collect::<Vec<Ray>>();
+        let straight = rays.iter().filter(|r| r.is_horizontal() || r.is_vertical()).cloned().collect::<Vec<Ray>>();
+
+        assert_eq!(count_overlaps(&straight), Ok(5));
+        assert_eq!(count_overlaps(&rays), Ok(12));
+    }
+
+    #[test]
+    fn scans_with_filters_thresholds_and_regions() {
+        let rays = EXAMPLE.lines().map(|l| Ray::from_str(l).unwrap()).collect::<Vec<Ray>>();
+        let cells = |scanner: VentScanner| scanner.scan(&rays).unwrap().into_iter()
+            .map(|(c, count)| (c.x, c.y, count))
+            .collect::<Vec<(usize, usize, u32)>>();
+
+        assert_eq!(cells(VentScanner::axis_aligned()), vec![(3, 4, 2), (7, 4, 2), (0, 9, 2), (1, 9, 2), (2, 9, 2)]);
+        assert_eq!(VentScanner::axis_aligned().count(&rays), Ok(5));
+        assert_eq!(VentScanner::new().count(&rays), Ok(12));
+        assert_eq!(cells(VentScanner::new().with_threshold(3)), vec![(4, 4, 3), (6, 4, 3)]);
+        assert_eq!(cells(VentScanner::new().with_slopes(&[Slope::Diagonal, Slope::AntiDiagonal])), vec![(5, 3, 2), (4, 4, 2), (6, 4, 2), (5, 5, 2)]);
+
+        let region = Region::new(Coordinate { x: 4, y: 5 }, Coordinate { x: 0, y: 0 });
+
+        assert_eq!(cells(VentScanner::new().with_region(region.clone())), vec![(2, 2, 2), (3, 4, 2), (4, 4, 3)]);
+        assert_eq!(VentScanner::new().with_region(region).count(&rays), Ok(3));
+    }
+
+    #[test]
+    fn clips_rays_to_the_scan_region() {
+        let mut rng = Rng::new(43);
+
+        for _ in 0..100 {
+            let rays = (0..rng.range(1, 12)).map(|_| random_ray(&mut rng, 16)).collect::<Vec<Ray>>();
+            let (a, b) = (random_ray(&mut rng, 16).src, random_ray(&mut rng, 16).src);
+            let region = Region::new(a, b);
+            let mut map = OceanVentMap::new();
+
+            for coordinate in rays.iter().flat_map(|ray| ray.points().unwrap()).filter(|c| region.contains(c)) {
+                map.increment(coordinate.x, coordinate.y);
+            }
+
+            let mut expected = map.vents.into_iter().filter(|(_, count)| *count >= 2).collect::<Vec<(Coordinate, u32)>>();
+            expected.sort_by_key(|(c, _)| (c.y, c.x));
+
+            assert_eq!(VentScanner::new().with_region(region).scan(&rays), Ok(expected), "{:?}", rays);
+        }
+
+        // Only the cells inside the region are visited, however long the rays are.
+        let rays = ["0,0 -> 3000000000,3000000000", "3000000000,0 -> 0,3000000000", "0,1500000000 -> 3000000000,1500000000"]
+            .iter().map(|l| Ray::from_str(l).unwrap()).collect::<Vec<Ray>>();
+        let region = Region::new(Coordinate { x: 1_499_999_990, y: 1_499_999_990 }, Coordinate { x: 1_500_000_010, y: 1_500_000_010 });
+
+        assert_eq!(VentScanner::new().with_region(region).scan(&rays), Ok(vec![(Coordinate { x: 1_500_000_000, y: 1_500_000_000 }, 3)]));
+    }
+
+    fn example_map() -> OceanVentMap {
+        let mut map = OceanVentMap::new();
+
+        for ray in EXAMPLE.lines().map(|l| Ray::from_str(l).unwrap()) {
+            for coordinate in ray.points().unwrap() {
+                map.increment(coordinate.x, coordinate.y);
+            }
+        }
+
+        map
+    }
+
+    #[test]
+    fn draws_the_example() {
+        let expected = "1.1....11.\n.111...2..\n..2.1.111.\n...1.2.2..\n.112313211\n...1.2....\n..1...1...\n.1.....1..\n1.......1.\n222111....\n";
+
+        assert_eq!(example_map().draw(), expected);
+    }
+
+    #[test]
+    fn renders_cropped_and_downsampled_heatmaps() {
+        let mut map = OceanVentMap::new();
+
+        for _ in 0..12 {
+            map.increment(1000, 2000);
+        }
+
+        map.increment(1002, 2001);
+        map.increment(1003, 2001);
+
+        assert_eq!(map.bounds(), Some(Region::new(Coordinate { x: 1000, y: 2000 }, Coordinate { x: 1003, y: 2001 })));
+        assert_eq!(HeatmapRenderer::new().render(&map), "#...\n..11\n");
+        assert_eq!(HeatmapRenderer::new().with_ramp(" .:#").render(&map), "#   \n  ..\n");
+        assert_eq!(HeatmapRenderer::new().with_max_size(2, 1).render(&map), "#1\n");
+
+        let viewport = Region::new(Coordinate { x: 2, y: 3 }, Coordinate { x: 4, y: 4 });
+
+        assert_eq!(HeatmapRenderer::new().with_viewport(viewport).render(&example_map()), ".1.\n123\n");
+        assert_eq!(HeatmapRenderer::gradient().with_max_size(1, 1).render(&map), "\x1b[38;2;255;64;0m\u{2588}\x1b[0m\n");
+        assert_eq!(HeatmapRenderer::new().render(&OceanVentMap::new()), "");
+    }
+
+    #[test]
+    fn keeps_counts_literal_on_the_default_palette() {
+        let mut map = OceanVentMap::new();
+
+        for (x, count) in [(0, 12), (1, 7), (2, 1), (3, 10)] {
+            for _ in 0..count {
+                map.increment(x, 0);
+            }
+        }
+
+        assert_eq!(HeatmapRenderer::new().render(&map), "#71#\n");
+        assert_eq!(HeatmapRenderer::new().with_ramp(".123456789#").render(&map), "#619\n");
+    }
+
+    #[test]
+    fn downsamples_huge_maps_by_default() {
+        let mut map = OceanVentMap::new();
+
+        map.increment(0, 0);
+        map.increment(3_000_000_000, 3_000_000_000);
+
+        let output = HeatmapRenderer::new().render(&map);
+        let lines = output.lines().collect::<Vec<&str>>();
+
+        assert_eq!(lines.len(), 1000);
+        assert!(lines.iter().all(|line| line.len() == 1000));
+        assert!(lines[0].starts_with('1') && lines[999].ends_with('1'));
+    }
+
+    #[test]
+    fn counts_dangerous_cells_in_rectangles() {
+        let map = example_map();
+        let index = map.index(2);
+        let region = |x1, y1, x2, y2| Region::new(Coordinate { x: x1, y: y1 }, Coordinate { x: x2, y: y2 });
+
+        assert_eq!(index.count_in(&region(0, 0, 9, 9)), 12);
+        assert_eq!(index.count_in(&region(0, 0, 100, 100)), 12);
+        assert_eq!(index.count_in(&region(2, 3, 4, 4)), 2);
+        assert_eq!(index.count_in(&region(0, 9, 2, 9)), 3);
+        assert_eq!(index.count_in(&region(50, 50, 60, 60)), 0);
+        assert_eq!(map.index(3).count_in(&region(0, 0, 9, 9)), 2);
+        assert_eq!(map.index(1).count_in(&region(0, 0, 9, 9)), map.vents.len() as u64);
+        assert_eq!(OceanVentMap::new().index(2).count_in(&region(0, 0, 9, 9)), 0);
+    }
+
+    #[test]
+    fn agrees_with_counting_cells() {
+        let mut rng = Rng::new(45);
+        let mut map = OceanVentMap::new();
+
+        for _ in 0..20 {
+            for coordinate in random_ray(&mut rng, 24).points().unwrap() {
+                map.increment(coordinate.x, coordinate.y);
+            }
+        }
+
+        let index = map.index(2);
+
+        for _ in 0..200 {
+            let region = Region::new(
+                Coordinate { x: rng.below(30) as usize, y: rng.below(30) as usize },
+                Coordinate { x: rng.below(30) as usize, y: rng.below(30) as usize },
+            );
+            let expected = map.vents.iter()
+                .filter(|(c, count)| **count >= 2 && region.contains(c))
+                .count() as u64;
+
+            assert_eq!(index.count_in(&region), expected);
+        }
+    }
+
+    #[test]
+    fn finds_the_densest_window() {
+        let index = example_map().index(2);
+
+        assert_eq!(index.densest_window(3, 3), Some((Region::new(Coordinate { x: 5, y: 3 }, Coordinate { x: 7, y: 5 }), 5)));
+        assert_eq!(index.densest_window(1, 1).map(|(_, count)| count), Some(1));
+        assert_eq!(index.densest_window(20, 20).map(|(_, count)| count), Some(12));
+        assert_eq!(index.densest_window(0, 3), None);
+        assert_eq!(OceanVentMap::new().index(2).densest_window(3, 3), None);
+    }
+
+    #[test]
+    fn finds_the_same_densest_window_as_sliding_every_window() {
+        let mut rng = Rng::new(46);
+
+        for _ in 0..20 {
+            let mut map = OceanVentMap::new();
+
+            for _ in 0..rng.range(1, 12) {
+                for coordinate in random_ray(&mut rng, 16).points().unwrap() {
+                    map.increment(coordinate.x, coordinate.y);
+                }
+            }
+
+            let index = map.index(2);
+            let b = map.bounds().unwrap();
+
+            for (width, height) in [(1, 1), (3, 2), (2, 5), (6, 6), (20, 4)] {
+                let (span_x, span_y) = (width.min(b.max.x - b.min.x + 1), height.min(b.max.y - b.min.y + 1));
+                let mut expected: Option<(Region, u64)> = None;
+
+                for y in b.min.y..=b.max.y + 1 - span_y {
+                    for x in b.min.x..=b.max.x + 1 - span_x {
+                        let window = Region::new(Coordinate { x, y }, Coordinate { x: x + width - 1, y: y + height - 1 });
+                        let count = map.vents.iter().filter(|(c, count)| **count >= 2 && window.contains(c)).count() as u64;
+
+                        if expected.as_ref().map(|(_, c)| count > *c).unwrap_or(true) {
+                            expected = Some((window, count));
+                        }
+                    }
+                }
+
+                assert_eq!(index.densest_window(width, height), expected);
+            }
+        }
+    }
+
+    #[test]
+    fn indexes_long_rays_in_space_proportional_to_their_cells() {
+        let mut map = OceanVentMap::new();
+
+        for coordinate in Ray::from_str("0,0 -> 99999,99999").unwrap().points().unwrap() {
+            map.increment(coordinate.x, coordinate.y);
+        }
+
+        let index = map.index(1);
+        let region = |x1, y1, x2, y2| Region::new(Coordinate { x: x1, y: y1 }, Coordinate { x: x2, y: y2 });
+
+        assert_eq!(index.count_in(&region(0, 0, 99_999, 99_999)), 100_000);
+        assert_eq!(index.count_in(&region(10, 0, 19, 14)), 5);
+        assert_eq!(index.densest_window(10, 10), Some((region(0, 0, 9, 9), 10)));
+    }
+
+    #[test]
+    fn indexes_vents_billions_of_cells_apart() {
+        let mut map = OceanVentMap::new();
+
+        for c in [(0, 0), (0, 0), (3_000_000_000, 5), (3_000_000_000, 5), (3_000_000_001, 6), (3_000_000_001, 6), (7, 4_000_000_000)] {
+            map.increment(c.0, c.1);
+        }
+
+        let index = map.index(2);
+        let region = |x1, y1, x2, y2| Region::new(Coordinate { x: x1, y: y1 }, Coordinate { x: x2, y: y2 });
+
+        assert_eq!(index.count_in(&region(0, 0, usize::MAX, usize::MAX)), 3);
+        assert_eq!(index.count_in(&region(1, 0, 3_000_000_000, 4_000_000_000)), 1);
+        assert_eq!(index.densest_window(2, 2), Some((region(3_000_000_000, 5, 3_000_000_001, 6), 2)));
+        assert_eq!(index.densest_window(1, 1), Some((region(0, 0, 0, 0), 1)));
+    }
+
+    #[test]
+    fn adds_and_removes_rays() {
+        let mut map = OceanVentMap::new();
+        let rays = EXAMPLE.lines().map(|l| Ray::from_str(l).unwrap()).collect::<Vec<Ray>>();
+        let updates = rays.iter().map(|ray| map.add_ray(ray.clone()).unwrap()).collect::<Vec<VentUpdate>>();
+
+        assert_eq!(map.overlaps(), 12);
+        assert_eq!(updates.iter().map(|u| u.delta).sum::<i64>(), 12);
+        assert_eq!(updates[1], VentUpdate { id: 1, overlaps: 0, delta: 0 });
+        assert_eq!(map.rays_covering(&Coordinate { x: 4, y: 4 }), &[1, 2, 8]);
+        assert_eq!(map.rays_covering(&Coordinate { x: 9, y: 9 }), &[] as &[usize]);
+
+        assert_eq!(map.remove_ray(8), Some(VentUpdate { id: 8, overlaps: 10, delta: -2 }));
+        assert_eq!(map.remove_ray(8), None);
+        assert!(map.ray(8).is_none());
+        assert_eq!(map.rays_covering(&Coordinate { x: 4, y: 4 }), &[1, 2]);
+        assert_eq!(map.vents.get(&Coordinate { x: 0, y: 0 }), None);
+
+        let remaining = rays.iter().enumerate().filter(|(i, _)| *i != 8).map(|(_, r)| r.clone()).collect::<Vec<Ray>>();
+
+        assert_eq!(count_overlaps(&remaining), Ok(map.overlaps()));
+        assert_eq!(map.add_ray(Ray::from_str("0,0 -> 4,2").unwrap()), Err(RaySlopeError::Unsupported { dx: 4, dy: 2 }));
+        assert_eq!(map.overlaps(), 10);
+    }
+
+    #[test]
+    fn agrees_with_rasterizing() {
+        let mut rng = Rng::new(5);
+
+        for _ in 0..200 {
+            let rays = (0..rng.range(1, 12)).map(|_| random_ray(&mut rng, 16)).collect::<Vec<Ray>>();
+
+            assert_eq!(count_overlaps(&rays), Ok(rasterized_overlaps(&rays)), "{:?}", rays);
+        }
+    }
+
+    #[test]
+    fn counts_thousands_of_rays_like_rasterizing() {
+        let mut rng = Rng::new(41);
+        let rays = (0..3000).map(|_| random_ray(&mut rng, 1000)).collect::<Vec<Ray>>();
+
+        assert_eq!(count_overlaps(&rays), Ok(rasterized_overlaps(&rays)));
+    }
+
+    // Thousands of long rays cross millions of times; counting them has to stay well
+    // under the time it takes to rasterize them. Timings only mean something optimised.
+    #[test]
+    #[ignore = "benchmark, run with cargo test --release -- --ignored"]
+    fn counts_thousands_of_rays_quickly() {
+        if cfg!(debug_assertions) {
+            panic!("the overlap counting benchmark needs --release");
+        }
+
+        let mut rng = Rng::new(41);
+        let rays = (0..3000).map(|_| random_ray(&mut rng, 1000)).collect::<Vec<Ray>>();
+        let start = std::time::Instant::now();
+
+        count_overlaps(&rays).unwrap();
+        assert!(start.elapsed().as_millis() < 250, "took {:?}", start.elapsed());
+    }
+
+    #[test]
+    fn counts_overlaps_with_huge_coordinates() {
+        let rays = ["0,0 -> 3000000000,0", "4000000000,0 -> 1000000000,0", "2000000000,5 -> 2000000000,0", "0,1 -> 1,0"]
+            .iter().map(|l| Ray::from_str(l).unwrap()).collect::<Vec<Ray>>();
+
+        assert_eq!(count_overlaps(&rays), Ok(2_000_000_002));
+    }
+
+    #[test]
+    fn rejects_unsupported_angles() {
+        let rays = vec![Ray::from_str("0,0 -> 2,0").unwrap(), Ray::from_str("0,0 -> 2,1").unwrap()];
+
+        assert_eq!(count_overlaps(&rays), Err(VentSweepError::UnsupportedRay(1)));
     }
 }
@@ -0,0 +1,278 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+use super::bit_string::{BitCursor, BitReadError, BitString, BitStringParseError};
+
+const LITERAL_TYPE_ID: u64 = 4;
+
+#[derive(PartialEq, Debug)]
+pub enum PacketParseError {
+    InvalidHex(BitStringParseError),
+    Truncated(BitReadError),
+    UnknownType(u64),
+    LiteralOverflow(usize),
+    InvalidOperandCount(Operator, usize),
+    SubpacketLengthMismatch { expected: usize, actual: usize },
+}
+
+#[derive(PartialEq, Debug)]
+pub enum PacketEvalError {
+    Overflow(Operator),
+    InvalidOperandCount(Operator, usize),
+}
+
+impl From<BitReadError> for PacketParseError {
+    fn from(e: BitReadError) -> Self {
+        PacketParseError::Truncated(e)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operator {
+    Sum,
+    Product,
+    Minimum,
+    Maximum,
+    GreaterThan,
+    LessThan,
+    EqualTo,
+}
+
+impl Operator {
+    fn from_type_id(type_id: u64) -> Result<Operator, PacketParseError> {
+        match type_id {
+            0 => Ok(Operator::Sum),
+            1 => Ok(Operator::Product),
+            2 => Ok(Operator::Minimum),
+            3 => Ok(Operator::Maximum),
+            5 => Ok(Operator::GreaterThan),
+            6 => Ok(Operator::LessThan),
+            7 => Ok(Operator::EqualTo),
+            t => Err(PacketParseError::UnknownType(t)),
+        }
+    }
+
+    fn is_comparison(&self) -> bool {
+        matches!(self, Operator::GreaterThan | Operator::LessThan | Operator::EqualTo)
+    }
+}
+
+impl Display for Operator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Operator::Sum => "sum",
+            Operator::Product => "product",
+            Operator::Minimum => "min",
+            Operator::Maximum => "max",
+            Operator::GreaterThan => "gt",
+            Operator::LessThan => "lt",
+            Operator::EqualTo => "eq",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PacketBody {
+    Literal(u64),
+    Operator(Operator, Vec<Packet>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Packet {
+    pub version: u8,
+    pub body: PacketBody,
+}
+
+impl Packet {
+    pub fn decode(bits: &BitString) -> Result<Packet, PacketParseError> {
+        Packet::read(&mut bits.cursor())
+    }
+
+    pub fn read(cursor: &mut BitCursor) -> Result<Packet, PacketParseError> {
+        let version = cursor.read_u64(3)? as u8;
+        let type_id = cursor.read_u64(3)?;
+
+        if type_id == LITERAL_TYPE_ID {
+            return Ok(Packet { version, body: PacketBody::Literal(Packet::read_literal(cursor)?) });
+        }
+
+        let operator = Operator::from_type_id(type_id)?;
+        let mut subpackets = Vec::new();
+
+        if cursor.read_bit()? {
+            let count = cursor.read_u64(11)?;
+
+            for _ in 0..count {
+                subpackets.push(Packet::read(cursor)?);
+            }
+        } else {
+            let length = cursor.read_u64(15)? as usize;
+            let end = cursor.position + length;
+
+            while cursor.position < end {
+                subpackets.push(Packet::read(cursor)?);
+            }
+
+            if cursor.position != end {
+                return Err(PacketParseError::SubpacketLengthMismatch {
+                    expected: length,
+                    actual: length + cursor.position - end,
+                });
+            }
+        }
+
+        if subpackets.is_empty() || (operator.is_comparison() && subpackets.len() != 2) {
+            return Err(PacketParseError::InvalidOperandCount(operator, subpackets.len()));
+        }
+
+        Ok(Packet { version, body: PacketBody::Operator(operator, subpackets) })
+    }
+
+    fn read_literal(cursor: &mut BitCursor) -> Result<u64, PacketParseError> {
+        let start = cursor.position;
+        let mut value: u64 = 0;
+
+        loop {
+            let more = cursor.read_bit()?;
+            let group = cursor.read_u64(4)?;
+
+            if value.leading_zeros() < 4 {
+                return Err(PacketParseError::LiteralOverflow(start));
+            }
+
+            value = (value << 4) | group;
+
+            if !more {
+                return Ok(value);
+            }
+        }
+    }
+
+    pub fn version_sum(&self) -> u64 {
+        self.version as u64 + match &self.body {
+            PacketBody::Literal(_) => 0,
+            PacketBody::Operator(_, subpackets) => subpackets.iter().map(|p| p.version_sum()).sum(),
+        }
+    }
+
+    pub fn evaluate(&self) -> Result<u64, PacketEvalError> {
+        match &self.body {
+            PacketBody::Literal(v) => Ok(*v),
+            PacketBody::Operator(operator, subpackets) => {
+                // Packets built by hand skip the checks made while reading.
+                if subpackets.is_empty() || (operator.is_comparison() && subpackets.len() != 2) {
+                    return Err(PacketEvalError::InvalidOperandCount(*operator, subpackets.len()));
+                }
+
+                let values = subpackets.iter().map(|p| p.evaluate()).collect::<Result<Vec<u64>, PacketEvalError>>()?;
+                let overflow = PacketEvalError::Overflow(*operator);
+
+                match operator {
+                    Operator::Sum => values.iter().try_fold(0_u64, |a, b| a.checked_add(*b)).ok_or(overflow),
+                    Operator::Product => values.iter().try_fold(1_u64, |a, b| a.checked_mul(*b)).ok_or(overflow),
+                    Operator::Minimum => Ok(values.iter().copied().min().unwrap_or(0)),
+                    Operator::Maximum => Ok(values.iter().copied().max().unwrap_or(0)),
+                    comparison => {
+                        let (a, b) = (values[0], values[1]);
+
+                        Ok((match comparison {
+                            Operator::GreaterThan => a > b,
+                            Operator::LessThan => a < b,
+                            _ => a == b,
+                        }) as u64)
+                    },
+                }
+            },
+        }
+    }
+
+    fn write_indented(&self, f: &mut Formatter<'_>, depth: usize) -> std::fmt::Result {
+        match &self.body {
+            PacketBody::Literal(v) => writeln!(f, "{:indent$}{} (v{})", "", v, self.version, indent = depth * 2),
+            PacketBody::Operator(operator, subpackets) => {
+                writeln!(f, "{:indent$}{} (v{})", "", operator, self.version, indent = depth * 2)?;
+
+                for packet in subpackets {
+                    packet.write_indented(f, depth + 1)?;
+                }
+
+                Ok(())
+            },
+        }
+    }
+}
+
+impl FromStr for Packet {
+    type Err = PacketParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Packet::decode(&BitString::from_hex(s).map_err(PacketParseError::InvalidHex)?)
+    }
+}
+
+impl Display for Packet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        self.write_indented(f, 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evaluate(hex: &str) -> Result<u64, PacketEvalError> {
+        Packet::from_str(hex).unwrap().evaluate()
+    }
+
+    #[test]
+    fn sums_versions() {
+        let sums = ["8A004A801A8002F478", "620080001611562C8802118E34", "C0015000016115A2E0802F182340", "A0016C880162017C3686B18A3D4780"]
+            .iter().map(|hex| Packet::from_str(hex).unwrap().version_sum()).collect::<Vec<u64>>();
+
+        assert_eq!(sums, vec![16, 12, 23, 31]);
+    }
+
+    #[test]
+    fn evaluates_the_examples() {
+        let examples = [
+            ("C200B40A82", 3), ("04005AC33890", 54), ("880086C3E88112", 7), ("CE00C43D881120", 9),
+            ("D8005AC2A8F0", 1), ("F600BC2D8F", 0), ("9C005AC2F8F0", 0), ("9C0141080250320F1802104A08", 1),
+        ];
+
+        for (hex, value) in examples {
+            assert_eq!(evaluate(hex), Ok(value), "{}", hex);
+        }
+    }
+
+    #[test]
+    fn decodes_literals_and_operators() {
+        assert_eq!(Packet::from_str("D2FE28"), Ok(Packet { version: 6, body: PacketBody::Literal(2021) }));
+        assert_eq!(Packet::from_str("38006F45291200").unwrap().to_string(), "lt (v1)\n  10 (v6)\n  20 (v2)\n");
+        assert_eq!(Packet::from_str("D2FE2"), Err(PacketParseError::Truncated(BitReadError::OutOfBounds { offset: 17, width: 4, len: 20 })));
+        assert!(matches!(Packet::from_str("D2FEZ8"), Err(PacketParseError::InvalidHex(_))));
+    }
+
+    #[test]
+    fn reports_overflow_instead_of_panicking() {
+        let literal = |v: u64| Packet { version: 0, body: PacketBody::Literal(v) };
+        let product = Packet { version: 0, body: PacketBody::Operator(Operator::Product, vec![literal(1 << 36), literal(1 << 36)]) };
+        let sum = Packet { version: 0, body: PacketBody::Operator(Operator::Sum, vec![literal(u64::MAX), literal(1)]) };
+
+        assert_eq!(product.evaluate(), Err(PacketEvalError::Overflow(Operator::Product)));
+        assert_eq!(sum.evaluate(), Err(PacketEvalError::Overflow(Operator::Sum)));
+    }
+
+    #[test]
+    fn rejects_built_packets_with_the_wrong_operand_count() {
+        let literal = |v: u64| Packet { version: 0, body: PacketBody::Literal(v) };
+        let operator = |o: Operator, subpackets: Vec<Packet>| Packet { version: 0, body: PacketBody::Operator(o, subpackets) };
+
+        assert_eq!(operator(Operator::LessThan, vec![]).evaluate(), Err(PacketEvalError::InvalidOperandCount(Operator::LessThan, 0)));
+        assert_eq!(operator(Operator::EqualTo, vec![literal(1)]).evaluate(), Err(PacketEvalError::InvalidOperandCount(Operator::EqualTo, 1)));
+        assert_eq!(operator(Operator::Minimum, vec![]).evaluate(), Err(PacketEvalError::InvalidOperandCount(Operator::Minimum, 0)));
+        assert_eq!(
+            operator(Operator::Sum, vec![operator(Operator::GreaterThan, vec![literal(1); 3])]).evaluate(),
+            Err(PacketEvalError::InvalidOperandCount(Operator::GreaterThan, 3)),
+        );
+    }
+}
@@ -0,0 +1,342 @@
+use std::fmt::{Display, Formatter};
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+use std::str::FromStr;
+
+#[derive(PartialEq, Debug)]
+pub enum PointParseError<E> {
+    InvalidFormat(E),
+    IncorrectValueCount(usize),
+}
+
+// Distances are computed as larger minus smaller so unsigned points work too.
+fn distance<T: Copy + PartialOrd + Sub<Output = T>>(a: T, b: T) -> T {
+    if a > b { a - b } else { b - a }
+}
+
+fn parse_values<T: FromStr>(s: &str, count: usize) -> Result<Vec<T>, PointParseError<T::Err>> {
+    let parts = s.trim().split(',').collect::<Vec<&str>>();
+
+    if parts.len() != count {
+        return Err(PointParseError::IncorrectValueCount(parts.len()));
+    }
+
+    parts.into_iter().map(|p| p.trim().parse::<T>().map_err(PointParseError::InvalidFormat)).collect()
+}
+
+// Points use screen orientation throughout the crate: x grows to the right and y
+// grows downwards, so UP is (0, -1) and a clockwise turn maps UP to RIGHT.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Point2<T> {
+    pub x: T,
+    pub y: T,
+}
+
+impl<T> Point2<T> {
+    pub const fn new(x: T, y: T) -> Point2<T> {
+        Point2 { x, y }
+    }
+}
+
+impl<T: Copy + PartialOrd + Add<Output = T> + Sub<Output = T>> Point2<T> {
+    pub fn manhattan(&self, other: &Point2<T>) -> T {
+        distance(self.x, other.x) + distance(self.y, other.y)
+    }
+
+    pub fn chebyshev(&self, other: &Point2<T>) -> T {
+        let (dx, dy) = (distance(self.x, other.x), distance(self.y, other.y));
+
+        if dx > dy { dx } else { dy }
+    }
+}
+
+impl<T: Copy + Neg<Output = T>> Point2<T> {
+    pub fn rotate_clockwise(&self) -> Point2<T> {
+        Point2 { x: -self.y, y: self.x }
+    }
+
+    pub fn rotate_counter_clockwise(&self) -> Point2<T> {
+        Point2 { x: self.y, y: -self.x }
+    }
+}
+
+macro_rules! signed_point2 {
+    ($($t:ty),*) => {$(
+        impl Point2<$t> {
+            pub const ORIGIN: Point2<$t> = Point2::new(0, 0);
+            pub const UP: Point2<$t> = Point2::new(0, -1);
+            pub const DOWN: Point2<$t> = Point2::new(0, 1);
+            pub const LEFT: Point2<$t> = Point2::new(-1, 0);
+            pub const RIGHT: Point2<$t> = Point2::new(1, 0);
+            pub const CARDINALS: [Point2<$t>; 4] = [Self::UP, Self::RIGHT, Self::DOWN, Self::LEFT];
+            pub const NEIGHBOURS: [Point2<$t>; 8] = [
+                Point2::new(-1, -1), Self::UP, Point2::new(1, -1), Self::RIGHT,
+                Point2::new(1, 1), Self::DOWN, Point2::new(-1, 1), Self::LEFT,
+            ];
+        }
+    )*};
+}
+
+signed_point2!(i32, i64);
+
+impl Point2<usize> {
+    // Moves by a signed offset, or None if that would leave the grid on the left or top.
+    pub fn offset(&self, delta: Point2<i64>) -> Option<Point2<usize>> {
+        Some(Point2 {
+            x: usize::try_from(self.x as i64 + delta.x).ok()?,
+            y: usize::try_from(self.y as i64 + delta.y).ok()?,
+        })
+    }
+}
+
+impl<T: Add<Output = T>> Add for Point2<T> {
+    type Output = Point2<T>;
+
+    fn add(self, rhs: Self) -> Point2<T> {
+        Point2 { x: self.x + rhs.x, y: self.y + rhs.y }
+    }
+}
+
+impl<T: Sub<Output = T>> Sub for Point2<T> {
+    type Output = Point2<T>;
+
+    fn sub(self, rhs: Self) -> Point2<T> {
+        Point2 { x: self.x - rhs.x, y: self.y - rhs.y }
+    }
+}
+
+impl<T: Copy + Mul<Output = T>> Mul<T> for Point2<T> {
+    type Output = Point2<T>;
+
+    fn mul(self, rhs: T) -> Point2<T> {
+        Point2 { x: self.x * rhs, y: self.y * rhs }
+    }
+}
+
+impl<T: Neg<Output = T>> Neg for Point2<T> {
+    type Output = Point2<T>;
+
+    fn neg(self) -> Point2<T> {
+        Point2 { x: -self.x, y: -self.y }
+    }
+}
+
+impl<T: Copy + Add<Output = T>> AddAssign for Point2<T> {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = Point2 { x: self.x + rhs.x, y: self.y + rhs.y };
+    }
+}
+
+impl<T: Copy + Sub<Output = T>> SubAssign for Point2<T> {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = Point2 { x: self.x - rhs.x, y: self.y - rhs.y };
+    }
+}
+
+impl<T> From<(T, T)> for Point2<T> {
+    fn from((x, y): (T, T)) -> Self {
+        Point2 { x, y }
+    }
+}
+
+impl<T> From<Point2<T>> for (T, T) {
+    fn from(p: Point2<T>) -> Self {
+        (p.x, p.y)
+    }
+}
+
+impl<T: FromStr> FromStr for Point2<T> {
+    type Err = PointParseError<T::Err>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut values = parse_values::<T>(s, 2)?.into_iter();
+
+        Ok(Point2 { x: values.next().unwrap(), y: values.next().unwrap() })
+    }
+}
+
+impl<T: Display> Display for Point2<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{},{}", self.x, self.y)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Point3<T> {
+    pub x: T,
+    pub y: T,
+    pub z: T,
+}
+
+impl<T> Point3<T> {
+    pub const fn new(x: T, y: T, z: T) -> Point3<T> {
+        Point3 { x, y, z }
+    }
+}
+
+impl<T: Copy + PartialOrd + Add<Output = T> + Sub<Output = T>> Point3<T> {
+    pub fn manhattan(&self, other: &Point3<T>) -> T {
+        distance(self.x, other.x) + distance(self.y, other.y) + distance(self.z, other.z)
+    }
+
+    pub fn chebyshev(&self, other: &Point3<T>) -> T {
+        let mut result = distance(self.x, other.x);
+
+        for d in [distance(self.y, other.y), distance(self.z, other.z)] {
+            if d > result {
+                result = d;
+            }
+        }
+
+        result
+    }
+}
+
+// Quarter turns about each axis, following the right-hand rule.
+impl<T: Copy + Neg<Output = T>> Point3<T> {
+    pub fn rotate_x(&self) -> Point3<T> {
+        Point3 { x: self.x, y: -self.z, z: self.y }
+    }
+
+    pub fn rotate_y(&self) -> Point3<T> {
+        Point3 { x: self.z, y: self.y, z: -self.x }
+    }
+
+    pub fn rotate_z(&self) -> Point3<T> {
+        Point3 { x: -self.y, y: self.x, z: self.z }
+    }
+}
+
+macro_rules! signed_point3 {
+    ($($t:ty),*) => {$(
+        impl Point3<$t> {
+            pub const ORIGIN: Point3<$t> = Point3::new(0, 0, 0);
+            pub const UNIT_X: Point3<$t> = Point3::new(1, 0, 0);
+            pub const UNIT_Y: Point3<$t> = Point3::new(0, 1, 0);
+            pub const UNIT_Z: Point3<$t> = Point3::new(0, 0, 1);
+            pub const AXES: [Point3<$t>; 6] = [
+                Self::UNIT_X, Point3::new(-1, 0, 0),
+                Self::UNIT_Y, Point3::new(0, -1, 0),
+                Self::UNIT_Z, Point3::new(0, 0, -1),
+            ];
+        }
+    )*};
+}
+
+signed_point3!(i32, i64);
+
+impl<T: Add<Output = T>> Add for Point3<T> {
+    type Output = Point3<T>;
+
+    fn add(self, rhs: Self) -> Point3<T> {
+        Point3 { x: self.x + rhs.x, y: self.y + rhs.y, z: self.z + rhs.z }
+    }
+}
+
+impl<T: Sub<Output = T>> Sub for Point3<T> {
+    type Output = Point3<T>;
+
+    fn sub(self, rhs: Self) -> Point3<T> {
+        Point3 { x: self.x - rhs.x, y: self.y - rhs.y, z: self.z - rhs.z }
+    }
+}
+
+impl<T: Copy + Mul<Output = T>> Mul<T> for Point3<T> {
+    type Output = Point3<T>;
+
+    fn mul(self, rhs: T) -> Point3<T> {
+        Point3 { x: self.x * rhs, y: self.y * rhs, z: self.z * rhs }
+    }
+}
+
+impl<T: Neg<Output = T>> Neg for Point3<T> {
+    type Output = Point3<T>;
+
+    fn neg(self) -> Point3<T> {
+        Point3 { x: -self.x, y: -self.y, z: -self.z }
+    }
+}
+
+impl<T: Copy + Add<Output = T>> AddAssign for Point3<T> {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = Point3 { x: self.x + rhs.x, y: self.y + rhs.y, z: self.z + rhs.z };
+    }
+}
+
+impl<T: Copy + Sub<Output = T>> SubAssign for Point3<T> {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = Point3 { x: self.x - rhs.x, y: self.y - rhs.y, z: self.z - rhs.z };
+    }
+}
+
+impl<T> From<(T, T, T)> for Point3<T> {
+    fn from((x, y, z): (T, T, T)) -> Self {
+        Point3 { x, y, z }
+    }
+}
+
+impl<T> From<Point3<T>> for (T, T, T) {
+    fn from(p: Point3<T>) -> Self {
+        (p.x, p.y, p.z)
+    }
+}
+
+impl<T: FromStr> FromStr for Point3<T> {
+    type Err = PointParseError<T::Err>;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut values = parse_values::<T>(s, 3)?.into_iter();
+
+        Ok(Point3 { x: values.next().unwrap(), y: values.next().unwrap(), z: values.next().unwrap() })
+    }
+}
+
+impl<T: Display> Display for Point3<T> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{},{},{}", self.x, self.y, self.z)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn does_arithmetic_and_distances() {
+        let a = Point2::new(3_i64, -2);
+        let b = Point2::new(-1_i64, 4);
+
+        assert_eq!(a + b, Point2::new(2, 2));
+        assert_eq!(a - b, Point2::new(4, -6));
+        assert_eq!(a * 3, Point2::new(9, -6));
+        assert_eq!(a.manhattan(&b), 10);
+        assert_eq!(a.chebyshev(&b), 6);
+        assert_eq!(Point2::new(2_usize, 9).manhattan(&Point2::new(5, 1)), 11);
+        assert_eq!(Point3::new(1_i32, 2, 3).manhattan(&Point3::new(-1, 2, 7)), 6);
+        assert_eq!(Point3::new(1_i32, 2, 3).chebyshev(&Point3::new(-1, 2, 7)), 4);
+    }
+
+    #[test]
+    fn rotates_and_offsets() {
+        assert_eq!(Point2::<i32>::UP.rotate_clockwise(), Point2::<i32>::RIGHT);
+        assert_eq!(Point2::<i32>::RIGHT.rotate_counter_clockwise(), Point2::<i32>::UP);
+        assert_eq!(Point2::<i32>::CARDINALS.iter().map(|d| d.rotate_clockwise()).collect::<Vec<_>>(), vec![
+            Point2::<i32>::RIGHT, Point2::<i32>::DOWN, Point2::<i32>::LEFT, Point2::<i32>::UP,
+        ]);
+        assert_eq!(Point3::<i64>::UNIT_X.rotate_z(), Point3::<i64>::UNIT_Y);
+        assert_eq!(Point3::<i64>::UNIT_Y.rotate_x(), Point3::<i64>::UNIT_Z);
+        assert_eq!(Point3::<i64>::UNIT_Z.rotate_y(), Point3::<i64>::UNIT_X);
+        assert_eq!(Point2::new(0_usize, 3).offset(Point2::<i64>::DOWN), Some(Point2::new(0, 4)));
+        assert_eq!(Point2::new(0_usize, 3).offset(Point2::<i64>::LEFT), None);
+    }
+
+    #[test]
+    fn parses_and_converts() {
+        assert_eq!("3,4".parse::<Point2<usize>>(), Ok(Point2::new(3, 4)));
+        assert_eq!("-1, 2, 7".parse::<Point3<i32>>(), Ok(Point3::new(-1, 2, 7)));
+        assert_eq!("3".parse::<Point2<usize>>(), Err(PointParseError::IncorrectValueCount(1)));
+        assert!(matches!("3,x".parse::<Point2<usize>>(), Err(PointParseError::InvalidFormat(_))));
+        assert_eq!(Point2::from((1, 2)), Point2::new(1, 2));
+        assert_eq!(<(i32, i32)>::from(Point2::new(1, 2)), (1, 2));
+        assert_eq!(Point2::new(1, 2).to_string(), "1,2");
+    }
+}
@@ -1,12 +1,15 @@
+use std::collections::VecDeque;
 use std::fs;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Lines};
+use std::marker::PhantomData;
+use std::ops::{Add, Sub};
+use std::str::FromStr;
 
-pub fn binary_to_number(input: Vec<char>) -> u32 {
-    input.iter().rev().enumerate().fold(0_u32, |result, (i, c)| {
-        match c {
-            '1' => result + 2_u32.pow(i as u32),
-            _ => result
-        }
-    })
+#[derive(Debug)]
+pub enum LineParseError<E> {
+    Io(std::io::Error),
+    Invalid { line: usize, text: String, error: E },
 }
 
 pub fn read_input(file: &str) -> String {
@@ -16,8 +19,111 @@ pub fn read_input(file: &str) -> String {
         .expect(format!("Unable to read the file {}", file).as_str());
 }
 
-pub fn transform_lines_to_integers(lines: &str) -> Vec<i32> {
-    return lines.lines().map(|l| l.parse::<i32>().unwrap()).collect();
+pub fn open_input(file: &str) -> BufReader<File> {
+    println!("Reading puzzle input: {}", file);
+
+    BufReader::new(File::open(file).unwrap_or_else(|_| panic!("Unable to read the file {}", file)))
+}
+
+// Blank lines are skipped, but still count towards the line numbers reported in errors.
+pub struct ParsedLines<R, T> {
+    lines: Lines<R>,
+    number: usize,
+    separator: Option<char>,
+    pending: VecDeque<String>,
+    marker: PhantomData<T>,
+}
+
+impl<R: BufRead, T: FromStr> Iterator for ParsedLines<R, T> {
+    type Item = Result<T, LineParseError<T::Err>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(e) => return Some(Err(LineParseError::Io(e))),
+            };
+
+            self.number += 1;
+            self.pending = match self.separator {
+                Some(separator) => line.split(separator).map(|v| v.trim().to_string()).filter(|v| !v.is_empty()).collect(),
+                None => Some(line.trim().to_string()).filter(|v| !v.is_empty()).into_iter().collect(),
+            };
+        }
+
+        let text = self.pending.pop_front()?;
+        let line = self.number;
+
+        Some(text.parse::<T>().map_err(|error| LineParseError::Invalid { line, text, error }))
+    }
+}
+
+pub fn parse_lines_from<T: FromStr, R: BufRead>(reader: R) -> ParsedLines<R, T> {
+    ParsedLines { lines: reader.lines(), number: 0, separator: None, pending: VecDeque::new(), marker: PhantomData }
+}
+
+pub fn parse_separated_from<T: FromStr, R: BufRead>(reader: R, separator: char) -> ParsedLines<R, T> {
+    ParsedLines { lines: reader.lines(), number: 0, separator: Some(separator), pending: VecDeque::new(), marker: PhantomData }
+}
+
+pub fn parse_lines<T: FromStr>(text: &str) -> Result<Vec<T>, LineParseError<T::Err>> {
+    parse_lines_from(text.as_bytes()).collect()
+}
+
+pub fn parse_comma_separated<T: FromStr>(text: &str) -> Result<Vec<T>, LineParseError<T::Err>> {
+    parse_separated_from(text.as_bytes(), ',').collect()
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Section {
+    pub start_line: usize,
+    pub lines: Vec<String>,
+}
+
+pub struct Sections<R> {
+    lines: Lines<R>,
+    number: usize,
+}
+
+impl<R: BufRead> Iterator for Sections<R> {
+    type Item = Result<Section, std::io::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut section = Section { start_line: 0, lines: Vec::new() };
+
+        for line in self.lines.by_ref() {
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e)),
+            };
+
+            self.number += 1;
+
+            if line.trim().is_empty() {
+                if section.lines.is_empty() {
+                    continue;
+                }
+
+                return Some(Ok(section));
+            }
+
+            if section.lines.is_empty() {
+                section.start_line = self.number;
+            }
+
+            section.lines.push(line);
+        }
+
+        Some(section).filter(|s| !s.lines.is_empty()).map(Ok)
+    }
+}
+
+pub fn sections_from<R: BufRead>(reader: R) -> Sections<R> {
+    Sections { lines: reader.lines(), number: 0 }
+}
+
+pub fn sections(text: &str) -> Vec<Section> {
+    sections_from(text.as_bytes()).map_while(Result::ok).collect()
 }
 
 pub fn first<T: Clone>(iterable: Vec<T>) -> Option<T> {
@@ -27,3 +133,185 @@ pub fn first<T: Clone>(iterable: Vec<T>) -> Option<T> {
 
     return None;
 }
+
+pub struct Windows<I: Iterator> {
+    iter: I,
+    size: usize,
+    buffer: VecDeque<I::Item>,
+}
+
+impl<I> Iterator for Windows<I> where I: Iterator, I::Item: Clone {
+    type Item = Vec<I::Item>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.size == 0 {
+            return None;
+        }
+
+        while self.buffer.len() < self.size {
+            self.buffer.push_back(self.iter.next()?);
+        }
+
+        let window = self.buffer.iter().cloned().collect();
+        self.buffer.pop_front();
+
+        Some(window)
+    }
+}
+
+pub struct RollingSums<I: Iterator> {
+    iter: I,
+    size: usize,
+    buffer: VecDeque<I::Item>,
+    total: Option<I::Item>,
+}
+
+impl<I> Iterator for RollingSums<I>
+    where I: Iterator, I::Item: Copy + Add<Output=I::Item> + Sub<Output=I::Item> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.size == 0 {
+            return None;
+        }
+
+        if self.buffer.len() == self.size {
+            let oldest = self.buffer.pop_front()?;
+            self.total = self.total.map(|t| t - oldest);
+        }
+
+        while self.buffer.len() < self.size {
+            let value = self.iter.next()?;
+
+            self.buffer.push_back(value);
+            self.total = Some(self.total.map_or(value, |t| t + value));
+        }
+
+        self.total
+    }
+}
+
+pub struct Pairwise<I: Iterator> {
+    iter: I,
+    previous: Option<I::Item>,
+}
+
+impl<I> Iterator for Pairwise<I> where I: Iterator, I::Item: Clone {
+    type Item = (I::Item, I::Item);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.previous.is_none() {
+            self.previous = Some(self.iter.next()?);
+        }
+
+        let current = self.iter.next()?;
+
+        self.previous.replace(current.clone()).map(|previous| (previous, current))
+    }
+}
+
+pub trait WindowedIterator: Iterator + Sized {
+    fn windows_of(self, size: usize) -> Windows<Self> {
+        Windows { iter: self, size, buffer: VecDeque::with_capacity(size) }
+    }
+
+    fn rolling_sums(self, size: usize) -> RollingSums<Self> {
+        RollingSums { iter: self, size, buffer: VecDeque::with_capacity(size), total: None }
+    }
+
+    fn pairwise(self) -> Pairwise<Self> {
+        Pairwise { iter: self, previous: None }
+    }
+
+    fn count_increases(self, lag: usize) -> usize where Self::Item: PartialOrd {
+        let mut buffer: VecDeque<Self::Item> = VecDeque::with_capacity(lag + 1);
+        let mut increases = 0;
+
+        for value in self {
+            buffer.push_back(value);
+
+            if buffer.len() > lag {
+                if let Some(earlier) = buffer.pop_front() {
+                    if buffer.back().is_some_and(|latest| *latest > earlier) {
+                        increases += 1;
+                    }
+                }
+            }
+        }
+
+        increases
+    }
+}
+
+impl<I: Iterator> WindowedIterator for I {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEPTHS: [u32; 10] = [199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
+
+    #[test]
+    fn reports_line_numbers_after_blank_lines() {
+        let parsed = parse_lines_from::<u32, _>("1\n\n  2  \n\nx\n".as_bytes()).collect::<Vec<_>>();
+
+        assert_eq!(parsed.len(), 3);
+        assert!(matches!(parsed[1], Ok(2)));
+        assert!(matches!(&parsed[2], Err(LineParseError::Invalid { line: 5, text, .. }) if text == "x"));
+        assert!(matches!(parse_lines::<u32>("\n\n7\n8"), Ok(v) if v == vec![7, 8]));
+    }
+
+    #[test]
+    fn parses_comma_lists_across_lines() {
+        assert!(matches!(parse_comma_separated::<u32>("3,4,\n3, 1\n\n,2\n"), Ok(v) if v == vec![3, 4, 3, 1, 2]));
+        assert!(matches!(
+            parse_comma_separated::<u32>("1,2\n3,y,4\n"),
+            Err(LineParseError::Invalid { line: 2, text, .. }) if text == "y"
+        ));
+    }
+
+    #[test]
+    fn splits_sections() {
+        let text = "\n\na\nb\n\n\n\nc\n\n";
+
+        assert_eq!(sections(text), vec![
+            Section { start_line: 3, lines: vec!["a".to_string(), "b".to_string()] },
+            Section { start_line: 8, lines: vec!["c".to_string()] },
+        ]);
+        assert_eq!(sections_from("x\ny".as_bytes()).map(|s| s.unwrap().start_line).collect::<Vec<usize>>(), vec![1]);
+        assert!(sections("\n  \n").is_empty());
+    }
+
+    #[test]
+    fn slides_windows() {
+        assert_eq!((1..=4).windows_of(3).collect::<Vec<Vec<i32>>>(), vec![vec![1, 2, 3], vec![2, 3, 4]]);
+        assert_eq!((1..=4).windows_of(4).count(), 1);
+        assert_eq!((1..=2).windows_of(3).count(), 0);
+        assert_eq!((1..=4).windows_of(0).count(), 0);
+        assert_eq!(std::iter::empty::<i32>().windows_of(1).count(), 0);
+    }
+
+    #[test]
+    fn sums_windows() {
+        assert_eq!(DEPTHS.iter().copied().rolling_sums(3).collect::<Vec<u32>>(), vec![607, 618, 618, 617, 647, 716, 769, 792]);
+        assert_eq!((1..=3).rolling_sums(1).collect::<Vec<i32>>(), vec![1, 2, 3]);
+        assert_eq!((1..=2).rolling_sums(3).count(), 0);
+        assert_eq!((1..=4).rolling_sums(0).count(), 0);
+    }
+
+    #[test]
+    fn pairs_neighbours() {
+        assert_eq!((1..=4).pairwise().collect::<Vec<(i32, i32)>>(), vec![(1, 2), (2, 3), (3, 4)]);
+        assert_eq!((1..=1).pairwise().count(), 0);
+        assert_eq!(std::iter::empty::<i32>().pairwise().count(), 0);
+    }
+
+    #[test]
+    fn counts_increases() {
+        assert_eq!(DEPTHS.iter().count_increases(1), 7);
+        assert_eq!(DEPTHS.iter().count_increases(3), 5);
+        assert_eq!(DEPTHS.iter().count_increases(0), 0);
+        assert_eq!(DEPTHS.iter().count_increases(10), 0);
+        assert_eq!(DEPTHS.iter().count_increases(9), 1);
+    }
+}